@@ -0,0 +1,170 @@
+// Matériau des blocs, à partir des sommets compressés (voir ATTRIBUTE_PACKED_VOXEL)
+// packed.x : x(0-4) y(5-9) z(10-14) normale(15-17) ao(18-19) ciel(20-23) blocs(24-27) surface(28)
+// packed.y : couche du tableau de textures (0-9), teinte sRGB r(10-15) g(16-21) b(22-27), opacité(28-31)
+#import bevy_pbr::mesh_functions::{get_world_from_local, mesh_position_local_to_world}
+#import bevy_pbr::view_transformations::position_world_to_clip
+#import bevy_pbr::mesh_view_bindings::{view, globals}
+
+struct VoxelParams {
+    tint: vec4<f32>,
+    sun: vec4<f32>,
+    fog_color: vec4<f32>,
+    sky_ambient: f32,
+    fog_start: f32,
+    fog_end: f32,
+    alpha_cutoff: f32,
+}
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(0) var<uniform> params: VoxelParams;
+@group(#{MATERIAL_BIND_GROUP}) @binding(1) var array_texture: texture_2d_array<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(2) var array_sampler: sampler;
+
+// Luminosité minimale, pour que les grottes ne soient pas complètement noires
+const MIN_BRIGHTNESS: f32 = 0.04;
+
+// Abaissement de la surface de l'eau sous le haut du bloc, et amplitude des vagues
+const WATER_SURFACE_DROP: f32 = 0.125;
+const WAVE_AMPLITUDE: f32 = 0.04;
+
+#ifdef VOXEL_MODEL
+// Faces des modèles de blocs : positions, normales et UVs flottantes ; dans packed,
+// seuls la lumière, la couche et la teinte sont utilisés
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+    @location(3) packed: vec2<u32>,
+};
+#else
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    @location(0) packed: vec2<u32>,
+};
+#endif
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) ao: f32,
+    @location(3) light: vec2<f32>,
+    @location(4) @interpolate(flat) layer: u32,
+    @location(5) world_position: vec3<f32>,
+    @location(6) tint: vec4<f32>,
+};
+
+fn unpack_position(word: u32) -> vec3<f32> {
+    return vec3<f32>(
+        f32(word & 31u),
+        f32((word >> 5u) & 31u),
+        f32((word >> 10u) & 31u),
+    );
+}
+
+// Même ordre que l'énumération Direction : Up, Down, North, South, East, West
+fn face_normal(index: u32) -> vec3<f32> {
+    var normals = array<vec3<f32>, 6>(
+        vec3<f32>(0.0, 1.0, 0.0),
+        vec3<f32>(0.0, -1.0, 0.0),
+        vec3<f32>(0.0, 0.0, -1.0),
+        vec3<f32>(0.0, 0.0, 1.0),
+        vec3<f32>(1.0, 0.0, 0.0),
+        vec3<f32>(-1.0, 0.0, 0.0),
+    );
+    return normals[min(index, 5u)];
+}
+
+// UVs en unités de blocs, projetées sur le plan de la face ; v descend le long
+// des faces verticales pour que les textures ne soient pas à l'envers
+fn face_uv(p: vec3<f32>, normal_index: u32) -> vec2<f32> {
+    switch normal_index {
+        case 0u: { return vec2<f32>(p.x, p.z); }
+        case 1u: { return vec2<f32>(p.x, -p.z); }
+        case 2u: { return vec2<f32>(-p.x, -p.y); }
+        case 3u: { return vec2<f32>(p.x, -p.y); }
+        case 4u: { return vec2<f32>(-p.z, -p.y); }
+        default: { return vec2<f32>(p.z, -p.y); }
+    }
+}
+
+// Hauteur des vagues, calculée en coordonnées du monde pour rester continue d'une section à l'autre
+fn wave_offset(world: vec3<f32>, time: f32) -> f32 {
+    let a = sin(world.x * 0.6 + time * 1.7);
+    let b = sin(world.z * 0.45 - time * 1.3);
+    let c = sin((world.x + world.z) * 1.1 + time * 2.3);
+    return (a + b + 0.5 * c) / 2.5 * WAVE_AMPLITUDE;
+}
+
+fn unpack_tint(word: u32) -> vec4<f32> {
+    let srgb = vec3<f32>(
+        f32((word >> 10u) & 63u),
+        f32((word >> 16u) & 63u),
+        f32((word >> 22u) & 63u),
+    ) / 63.0;
+    // Approximation de la conversion sRGB -> linéaire, suffisante pour une teinte
+    return vec4<f32>(pow(srgb, vec3<f32>(2.2)), f32((word >> 28u) & 15u) / 15.0);
+}
+
+fn ao_brightness(ao: u32) -> f32 {
+    var levels = array<f32, 4>(0.45, 0.65, 0.82, 1.0);
+    return levels[ao & 3u];
+}
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    let word = vertex.packed.x;
+#ifdef VOXEL_MODEL
+    let position = vertex.position;
+#else
+    let position = unpack_position(word);
+    let normal_index = (word >> 15u) & 7u;
+#endif
+
+    var world = mesh_position_local_to_world(
+        get_world_from_local(vertex.instance_index),
+        vec4<f32>(position, 1.0),
+    );
+    if ((word >> 28u) & 1u) == 1u {
+        world.y += wave_offset(world.xyz, globals.time) - WATER_SURFACE_DROP;
+    }
+
+    var out: VertexOutput;
+    out.clip_position = position_world_to_clip(world.xyz);
+    out.world_position = world.xyz;
+    // Les sections ne sont que translatées (et agrandies uniformément en LOD) : la normale locale est aussi celle du monde
+#ifdef VOXEL_MODEL
+    out.uv = vertex.uv;
+    out.normal = vertex.normal;
+#else
+    out.uv = face_uv(position, normal_index);
+    out.normal = face_normal(normal_index);
+#endif
+    out.ao = ao_brightness((word >> 18u) & 3u);
+    out.light = vec2<f32>(f32((word >> 20u) & 15u), f32((word >> 24u) & 15u)) / 15.0;
+    out.layer = vertex.packed.y & 0x3FFu;
+    out.tint = unpack_tint(vertex.packed.y);
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(array_texture, array_sampler, in.uv, in.layer) * in.tint * params.tint;
+    if color.a < params.alpha_cutoff {
+        discard;
+    }
+
+    // Lumière du ciel : une part ambiante, le reste selon l'orientation vers le soleil
+    let sun = max(dot(in.normal, params.sun.xyz), 0.0) * params.sun.w;
+    let sky = in.light.x * mix(sun, 1.0, params.sky_ambient) * max(params.sun.w, 0.15);
+    let brightness = max(max(sky, in.light.y), MIN_BRIGHTNESS) * in.ao;
+
+    var lit = vec4<f32>(color.rgb * brightness, color.a);
+
+    // Brouillard linéaire (sous l'eau par exemple) : la couleur et l'opacité tendent vers celles du brouillard
+    let distance = length(in.world_position - view.world_position);
+    let fog = clamp((distance - params.fog_start) / max(params.fog_end - params.fog_start, 0.001), 0.0, 1.0) * params.fog_color.a;
+    lit = vec4<f32>(mix(lit.rgb, params.fog_color.rgb, fog), mix(lit.a, 1.0, fog));
+
+    return lit;
+}
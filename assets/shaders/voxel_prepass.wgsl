@@ -0,0 +1,92 @@
+// Passe de profondeur (ombres) des blocs : seule la position est décodée.
+// La surface de l'eau est abaissée comme dans voxel.wgsl, sans les vagues.
+// Pour les couches qui peuvent jeter des pixels (MAY_DISCARD : blocs découpés comme les feuilles),
+// la texture est lue et les pixels sous le seuil sont jetés, comme dans voxel.wgsl.
+#import bevy_pbr::mesh_functions::{get_world_from_local, mesh_position_local_to_clip}
+
+struct VoxelParams {
+    tint: vec4<f32>,
+    sun: vec4<f32>,
+    fog_color: vec4<f32>,
+    sky_ambient: f32,
+    fog_start: f32,
+    fog_end: f32,
+    alpha_cutoff: f32,
+}
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(0) var<uniform> params: VoxelParams;
+@group(#{MATERIAL_BIND_GROUP}) @binding(1) var array_texture: texture_2d_array<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(2) var array_sampler: sampler;
+
+#ifdef VOXEL_MODEL
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    @location(0) position: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+    @location(3) packed: vec2<u32>,
+};
+#else
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    @location(0) packed: vec2<u32>,
+};
+#endif
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) @interpolate(flat) layer: u32,
+    // Opacité de la teinte du sommet
+    @location(2) alpha: f32,
+};
+
+// Mêmes UVs que face_uv dans voxel.wgsl
+fn face_uv(p: vec3<f32>, normal_index: u32) -> vec2<f32> {
+    switch normal_index {
+        case 0u: { return vec2<f32>(p.x, p.z); }
+        case 1u: { return vec2<f32>(p.x, -p.z); }
+        case 2u: { return vec2<f32>(-p.x, -p.y); }
+        case 3u: { return vec2<f32>(p.x, -p.y); }
+        case 4u: { return vec2<f32>(-p.z, -p.y); }
+        default: { return vec2<f32>(p.z, -p.y); }
+    }
+}
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    let word = vertex.packed.x;
+    var out: VertexOutput;
+#ifdef VOXEL_MODEL
+    var position = vertex.position;
+    out.uv = vertex.uv;
+#else
+    var position = vec3<f32>(
+        f32(word & 31u),
+        f32((word >> 5u) & 31u),
+        f32((word >> 10u) & 31u),
+    );
+    out.uv = face_uv(position, (word >> 15u) & 7u);
+#endif
+    out.layer = vertex.packed.y & 0x3FFu;
+    out.alpha = f32((vertex.packed.y >> 28u) & 15u) / 15.0;
+
+    if ((word >> 28u) & 1u) == 1u {
+        position.y -= 0.125;
+    }
+
+    out.clip_position = mesh_position_local_to_clip(
+        get_world_from_local(vertex.instance_index),
+        vec4<f32>(position, 1.0),
+    );
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) {
+#ifdef MAY_DISCARD
+    let alpha = textureSample(array_texture, array_sampler, in.uv, in.layer).a * in.alpha * params.tint.a;
+    if alpha < params.alpha_cutoff {
+        discard;
+    }
+#endif
+}
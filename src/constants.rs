@@ -1,8 +1,5 @@
 pub const CHUNK_SIZE: usize = 16;
 pub const SECTION_HEIGHT: usize = 16;
-pub const WORLD_HEIGHT: usize = 384; // 4 sections verticales (64 = 16 * 4)
-pub const WORLD_SIZE: usize = 2000;
 
-pub const SEA_LEVEL: usize = 126;
-
-pub  const VIEW_DISTANCE: i32 = 20;
\ No newline at end of file
+// Les dimensions du monde (hauteurs, niveau de la mer, bordure, distance de vue)
+// sont dans WorldConfig (world/world_config.rs)
@@ -1,4 +1,3 @@
-use crate::constants::{SEA_LEVEL, WORLD_HEIGHT};
 use crate::world::block::BlockType;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -25,79 +24,86 @@ pub struct Biome {
     pub humidity: f64,
     pub continentalness: f64,
 
-    pub base_height: f64,   // Hauteur moyenne
+    pub base_height: f64,   // Hauteur moyenne, par rapport au niveau de la mer
     pub amplitude: f64,     // Variation de hauteur (relief)
     pub frequency: f64,     // Fréquence du bruit (rugosité)
     pub size_factor: f64,
     pub surface_block: BlockType,  // ID du bloc de surface (ex: herbe)
     pub underground_block: BlockType, // ID du bloc sous-jacent (ex: terre)
+    /// Couleur sRGB de l'eau du biome
+    pub water_color: [f32; 3],
 }
 
 pub fn get_biome_data(biome_type: BiomeType) -> Biome {
     match biome_type {
         BiomeType::Mountain => Biome {
-            temperature: 0.0,
-            humidity: 0.0,
+            temperature: 0.2,
+            humidity: 0.3,
             continentalness: 1.0,
 
-            base_height: (SEA_LEVEL + 10) as f64,
+            base_height: 10.0,
             amplitude: 200.0,
             frequency: 0.1,
             size_factor: 1.5,
-            surface_block: BlockType::Rock,
-            underground_block: BlockType::Rock,
+            surface_block: BlockType::ROCK,
+            underground_block: BlockType::ROCK,
+            water_color: [0.14, 0.25, 0.42],
         },
         BiomeType::Plain => Biome {
-            temperature: 0.0,
-            humidity: 0.0,
+            temperature: 0.8,
+            humidity: 0.4,
             continentalness: 0.5,
 
-            base_height: (SEA_LEVEL + 4) as f64,
+            base_height: 4.0,
             amplitude: 5.0,
             frequency: 0.02,
             size_factor: 1.0,
 
-            surface_block: BlockType::Grass,
-            underground_block: BlockType::Rock,
+            surface_block: BlockType::GRASS,
+            underground_block: BlockType::ROCK,
+            water_color: [0.16, 0.30, 0.50],
         },
         BiomeType::Beach => Biome {
-            temperature: 0.0,
-            humidity: 0.0,
+            temperature: 0.8,
+            humidity: 0.4,
             continentalness: 0.0,
 
-            base_height: (SEA_LEVEL + 4) as f64,
+            base_height: 4.0,
             amplitude: 5.0,
             frequency: 0.02,
             size_factor: 1.0,
 
-            surface_block: BlockType::Sand,
-            underground_block: BlockType::Rock,
+            surface_block: BlockType::SAND,
+            underground_block: BlockType::ROCK,
+            water_color: [0.18, 0.38, 0.55],
         },
         BiomeType::Ocean => Biome {
-            temperature: 0.0,
-            humidity: 1.0,
+            temperature: 0.5,
+            humidity: 0.5,
             continentalness: -0.1,
 
-            base_height: (SEA_LEVEL - 80) as f64,  // Niveau bas, sous la mer
+            base_height: -80.0,  // Niveau bas, sous la mer
             amplitude: 25.0,
             frequency: 0.005,
             size_factor: 2.0,
 
-            surface_block: BlockType::Air,
-            underground_block: BlockType::Sand, // Ou terre meuble sous l'eau
+            surface_block: BlockType::AIR,
+            underground_block: BlockType::SAND, // Ou terre meuble sous l'eau
+            water_color: [0.12, 0.22, 0.45],
         },
         BiomeType::Abyss => Biome {
-            temperature: 0.0,
-            humidity: 1.0,
+            temperature: 0.5,
+            humidity: 0.5,
             continentalness: -0.5,
 
-            base_height: (SEA_LEVEL - 110) as f64,  // Niveau bas, sous la mer
+            base_height: -110.0,  // Niveau bas, sous la mer
             amplitude: 100.0,
             frequency: 0.005,
             size_factor: 1.0,
 
-            surface_block: BlockType::Air,
-            underground_block: BlockType::Rock, // Ou terre meuble sous l'eau
+            surface_block: BlockType::AIR,
+            underground_block: BlockType::ROCK, // Ou terre meuble sous l'eau
+            water_color: [0.07, 0.12, 0.30],
         },
     }
 }
@@ -1,4 +1,3 @@
-use std::collections::VecDeque;
 use std::sync::Arc;
 use bevy::app::{App, Plugin, Startup, Update};
 use bevy::log::info;
@@ -6,20 +5,23 @@ use bevy::prelude::{Commands, Event, EventReader, EventWriter, Res, ResMut, Reso
 use bevy::tasks::{AsyncComputeTaskPool, Task};
 use futures::FutureExt;
 use noise::Perlin;
-use crate::constants::WORLD_SIZE;
 use crate::render::chunk_loadings_mesh_logic::ChunkToUpdateEvent;
+use crate::world::chunk_loadings_logic::ChunkWorkStats;
+use crate::world::chunk_queue::{ChunkJobKind, ChunkWorkQueue};
+use crate::world::chunk_state::{ChunkStates, ChunkStatus};
 use crate::world::load_save_chunk::{load_chunk, WorldData};
 use crate::generation::biome::{Biome, BiomeType, get_biome_data};
 use crate::generation::generate_biome_map::{BiomeMap};
 use crate::generation::generate_chunk::generate_chunk;
 use crate::generation::generate_height_map::HeightMap;
 use crate::world::chunk::Chunk;
+use crate::world::world_config::WorldConfig;
 
 pub struct ChunkGenerationPlugin;
 
+/// Générations en cours ; les demandes en attente sont dans `ChunkWorkQueue`
 #[derive(Default, Resource)]
 pub struct ChunkGenerateQueue {
-    pub queue: VecDeque<ToGenerateChunkEvent>,
     pub current_tasks: Vec<Task<(i32, i32, Chunk)>>, // plusieurs tâches en parallèle
 }
 
@@ -58,7 +60,7 @@ impl Plugin for ChunkGenerationPlugin {
 }
 
 /// Initialisation de la map de biomes (à faire une fois au démarrage)
-fn setup_maps(mut commands: Commands) {
+fn setup_maps(mut commands: Commands, config: Res<WorldConfig>) {
     let mut map = BiomeMap::new();
     commands.insert_resource(BiomeMapArc(Arc::new(map.clone())));
 
@@ -67,21 +69,16 @@ fn setup_maps(mut commands: Commands) {
 
     //let mut height_map = HeightMap::new();
     //height_map.generate(-69, 47, &map);
-    commands.insert_resource(HeightMap::new());
+    commands.insert_resource(HeightMap::new(config.sea_level));
 
 }
 
 fn enqueue_generate_requests(
-    mut queue: ResMut<ChunkGenerateQueue>,
+    mut work_queue: ResMut<ChunkWorkQueue>,
     mut event_reader: EventReader<ToGenerateChunkEvent>,
 ) {
     for event in event_reader.read() {
-        // Optionnel : éviter les doublons dans la file
-        if !queue.queue.iter().any(|e| e.x == event.x && e.z == event.z) &&
-            !queue.current_tasks.iter().any(|_| false) // tu peux affiner pour éviter doublons dans current_tasks
-        {
-            queue.queue.push_back(event.clone());
-        }
+        work_queue.push(event.x, event.z, ChunkJobKind::Generate);
     }
 }
 
@@ -91,20 +88,23 @@ const MAX_CONCURRENT_TASKS: usize = 5;
 fn generate_chunks_system(
     biome_map: Res<BiomeMapArc>,
     height_map: Res<HeightMap>,
+    world_data: Res<WorldData>,
     mut queue: ResMut<ChunkGenerateQueue>,
+    mut work_queue: ResMut<ChunkWorkQueue>,
 ) {
     let task_pool = AsyncComputeTaskPool::get();
 
     while queue.current_tasks.len() < MAX_CONCURRENT_TASKS {
-        if let Some(event) = queue.queue.pop_front() {
-            let x = event.x;
-            let z = event.z;
+        if let Some(job) = work_queue.pop(ChunkJobKind::Generate) {
+            let x = job.x;
+            let z = job.z;
             let biome_map = biome_map.0.clone();
             let height_map = height_map.clone();
+            let config = world_data.config.clone();
 
             let task = task_pool.spawn(async move {
                 let perlin = Perlin::new(0);
-                let chunk = generate_chunk(x, z, &perlin, &biome_map, &height_map).await;
+                let chunk = generate_chunk(x, z, &config, &perlin, &biome_map, &height_map).await;
                 (x, z, chunk)
             });
 
@@ -133,12 +133,24 @@ fn apply_generate_chunks(
     mut generate_events: EventReader<ChunkGenerateEvent>,
     mut to_update_mesh: EventWriter<ChunkToUpdateEvent>,
     mut world_data: ResMut<WorldData>,
+    mut states: ResMut<ChunkStates>,
+    mut stats: ResMut<ChunkWorkStats>,
 ) {
     for event in generate_events.read() {
         let x = event.x;
         let z = event.z;
 
-        world_data.chunks_loaded.insert((x, z), event.chunk.clone());
+        // Chunk déchargé pendant la génération
+        if !states.is(x, z, ChunkStatus::Generating) {
+            stats.generations_discarded += 1;
+            continue;
+        }
+
+        world_data.chunks_loaded.insert((x, z), Arc::new(event.chunk.clone()));
+        world_data.stitch_light_borders(x, z);
+        states.set(x, z, ChunkStatus::Lit);
         to_update_mesh.write(ChunkToUpdateEvent { x, z });
+        // Les faces de bordure des voisins peuvent maintenant être cachées
+        to_update_mesh.write_batch(world_data.loaded_neighbors(x, z).map(|(x, z)| ChunkToUpdateEvent { x, z }));
     }
 }
\ No newline at end of file
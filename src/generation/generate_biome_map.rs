@@ -5,7 +5,7 @@ use image::{ImageBuffer, Luma, Rgb, RgbImage};
 use noise::{NoiseFn, Perlin, Fbm, OpenSimplex};
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaCha8Rng;
-use crate::constants::{CHUNK_SIZE, SEA_LEVEL, WORLD_HEIGHT};
+use crate::constants::CHUNK_SIZE;
 use crate::generation::biome::{ALL_BIOMES, BiomeType, get_biome_data};
 
 
@@ -290,14 +290,23 @@ impl BiomeMap {
 
 
     pub fn get_biome(&self, x_block: i64, z_block: i64) -> BiomeType {
+        let (x_index, z_index) = self.cell_of(x_block, z_block);
+        self.biomes_map[z_index][x_index]
+    }
+
+    /// Remplace le biome de toute la case (256 x 256 blocs) qui contient le bloc (x, z)
+    pub fn set_biome(&mut self, x_block: i64, z_block: i64, biome: BiomeType) {
+        let (x_index, z_index) = self.cell_of(x_block, z_block);
+        self.biomes_map[z_index][x_index] = biome;
+    }
+
+    fn cell_of(&self, x_block: i64, z_block: i64) -> (usize, usize) {
         let biome_size = 256;
         let map_size = self.biomes_map.len() as i32; // ici 1600
 
         // calcul position case relative à 0,0
         let x_index = (x_block.div_euclid(biome_size)) + (map_size / 2) as i64;
         let z_index = (z_block.div_euclid(biome_size)) + (map_size / 2) as i64;
-
-
-        self.biomes_map[z_index as usize][x_index as usize]
+        (x_index as usize, z_index as usize)
     }
 }
@@ -1,24 +1,21 @@
 use noise::Perlin;
-use crate::constants::{CHUNK_SIZE, SEA_LEVEL, SECTION_HEIGHT, WORLD_HEIGHT, WORLD_SIZE};
+use crate::constants::{CHUNK_SIZE, SECTION_HEIGHT};
 use crate::generation::biome::{BiomeType, get_biome_data};
 use crate::generation::generate_biome_map::BiomeMap;
 use crate::generation::generate_height_map::HeightMap;
 use crate::world::block::BlockType;
 use crate::world::chunk::{Chunk, ChunkSection};
+use crate::world::light::light_chunk;
+use crate::world::world_config::WorldConfig;
 
 
 /// Version modifiée de generate_chunk pour prendre Perlin & BiomeMap en référence
-pub async fn generate_chunk(x: i32, z: i32, perlin: &Perlin, biomes_map: &BiomeMap, height_map: &HeightMap) -> Chunk {
-    // Palette vide au départ, qui sera clonée pour chaque section
-    let palette = vec![];
+pub async fn generate_chunk(x: i32, z: i32, config: &WorldConfig, perlin: &Perlin, biomes_map: &BiomeMap, height_map: &HeightMap) -> Chunk {
+    // Sections vides (palette vide, aucune lumière) au départ
     let mut sections: Vec<ChunkSection> = vec![];
 
-    for section_y in 0..(WORLD_HEIGHT / SECTION_HEIGHT) {
-        sections.push(ChunkSection {
-            y: section_y as i8,
-            blocks: vec![0; CHUNK_SIZE * CHUNK_SIZE * SECTION_HEIGHT],
-            palette: palette.clone(),
-        });
+    for section_index in 0..config.section_count() {
+        sections.push(ChunkSection::new(config.min_section() + section_index as i8));
     }
 
     let heightmap = height_map.get_chunk(x as i64, z as i64, &biomes_map);
@@ -30,49 +27,41 @@ pub async fn generate_chunk(x: i32, z: i32, perlin: &Perlin, biomes_map: &BiomeM
 
             let biome = biomes_map.get_biome(world_x, world_z);
             let biome_data = get_biome_data(biome);
-            let height = heightmap[local_x][local_z] as usize;
+            let height = heightmap[local_x][local_z] as i32;
 
-            for y in 0..WORLD_HEIGHT {
-                let section_index = y / SECTION_HEIGHT;
-                let local_y = y % SECTION_HEIGHT;
+            for y in config.min_build_height..config.max_build_height {
+                let section_index = ((y - config.min_build_height) as usize) / SECTION_HEIGHT;
+                let local_y = y.rem_euclid(SECTION_HEIGHT as i32) as usize;
                 let block_index = local_y * CHUNK_SIZE * CHUNK_SIZE + local_z * CHUNK_SIZE + local_x;
 
                 let block_type= if biome == BiomeType::Ocean || biome == BiomeType::Abyss{
                     if y <= height {
                         biome_data.underground_block
                     }
-                    else if y <= SEA_LEVEL {
-                        BlockType::Water
+                    else if y <= config.sea_level {
+                        BlockType::WATER
                     } else {
-                        BlockType::Air
+                        BlockType::AIR
                     }
                 } else {
                     if y > height {
-                        BlockType::Air
+                        BlockType::AIR
                     } else if y == height {
                         biome_data.surface_block.clone()
                     } else if y >= height - 3 {
                         biome_data.underground_block.clone()
                     } else {
-                        BlockType::Rock
+                        BlockType::ROCK
                     }
                 };
 
-                let block_id = get_or_insert_block_id(&mut sections[section_index].palette, &block_type);
-                sections[section_index].blocks[block_index] = block_id as u8;
+                let block_id = sections[section_index].palette_index(block_type);
+                sections[section_index].blocks[block_index] = block_id;
             }
         }
     }
 
-    Chunk { x, z, sections }
-}
-
-fn get_or_insert_block_id(palette: &mut Vec<String>, block_type: &BlockType) -> usize {
-    let block_name = block_type.to_string();
-    if let Some(index) = palette.iter().position(|b| *b == block_name) {
-        index
-    } else {
-        palette.push(block_name.to_string());
-        palette.len() - 1
-    }
+    let mut chunk = Chunk { x, z, sections };
+    light_chunk(&mut chunk);
+    chunk
 }
\ No newline at end of file
@@ -3,19 +3,21 @@ use bevy::math::IVec2;
 use bevy::prelude::Resource;
 use crate::generation::biome::{Biome, BiomeType, get_biome_data};
 use noise::{Fbm, NoiseFn, Perlin};
-use crate::constants::{CHUNK_SIZE, WORLD_HEIGHT};
+use crate::constants::CHUNK_SIZE;
 use crate::generation::generate_biome_map::{BiomeMap};
 use crate::world::chunk::Chunk;
 
-#[derive(Resource, Default, Clone)]
+#[derive(Resource, Clone)]
 pub struct HeightMap {
+    /// Les hauteurs des biomes sont relatives au niveau de la mer
+    sea_level: i32,
 }
 
 
 impl HeightMap{
 
-    pub fn new() -> Self {
-        Self {  }
+    pub fn new(sea_level: i32) -> Self {
+        Self { sea_level }
     }
 
     pub fn get_chunk(&self, chunk_x: i64, chunk_z: i64, biomes_map: &BiomeMap) -> Vec<Vec<usize>> {
@@ -52,7 +54,7 @@ impl HeightMap{
                 fbm.octaves = 5;
                 fbm.frequency = current_biome_data.frequency;
 
-                chunk_heightmap[local_x][local_z] = (current_biome_data.base_height + fbm.get([world_x as f64 * current_biome_data.frequency, world_z as f64 * current_biome_data.frequency]) * current_biome_data.amplitude) as usize;
+                chunk_heightmap[local_x][local_z] = (self.sea_level as f64 + current_biome_data.base_height + fbm.get([world_x as f64 * current_biome_data.frequency, world_z as f64 * current_biome_data.frequency]) * current_biome_data.amplitude) as usize;
             }
         }
         chunk_heightmap
@@ -12,7 +12,9 @@ use bevy_pbr::{Atmosphere, AtmosphereSettings, VolumetricFog};
 use bevy_rapier3d::prelude::*;
 use crate::camera::MovementSettings;
 use crate::world::block::BlockType;
+use crate::world::chunk_loadings_logic::ChunkLoader;
 use crate::world::load_save_chunk::WorldData;
+use crate::world::world_config::WorldConfig;
 
 #[derive(Component, PartialEq, Eq)]
 pub enum PlayerMode {
@@ -32,15 +34,15 @@ impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(AutoExposurePlugin)
             .add_systems(Startup, spawn_player)
-            .add_systems(Update, (player_movement, toggle_spectator_mode));
+            .add_systems(Update, (player_movement, toggle_spectator_mode, keep_player_in_world));
     }
 }
 
-fn spawn_player(mut commands: Commands) {
+fn spawn_player(mut commands: Commands, config: Res<WorldConfig>) {
 
     let player = commands
         .spawn((
-            Transform::from_xyz(1500.0, 258.0, 1000.0),
+            Transform::from_xyz(1500.0, (config.sea_level + 132) as f32, 1000.0),
             RigidBody::Dynamic,
             Collider::capsule_y(1.8, 0.5),
             Velocity::zero(),
@@ -48,6 +50,8 @@ fn spawn_player(mut commands: Commands) {
             GravityScale(1.0),
             Player,
             PlayerMode::Normal,
+            // Rayon recalé sur la distance de vue réglée en jeu
+            ChunkLoader { radius: config.view_distance },
         ))
         .id();
 
@@ -182,3 +186,22 @@ fn toggle_spectator_mode(
     }
 }
 
+
+/// Garde le joueur dans les limites du monde : bloqué à la bordure,
+/// et replacé au-dessus du niveau de la mer s'il tombe sous le monde
+fn keep_player_in_world(
+    config: Res<WorldConfig>,
+    mut query: Query<(&mut Transform, &mut Velocity), With<Player>>,
+) {
+    let border = config.world_border as f32;
+
+    for (mut transform, mut velocity) in query.iter_mut() {
+        transform.translation.x = transform.translation.x.clamp(-border, border);
+        transform.translation.z = transform.translation.z.clamp(-border, border);
+
+        if transform.translation.y < (config.min_build_height - 64) as f32 {
+            transform.translation.y = (config.sea_level + 132) as f32;
+            velocity.linvel = Vec3::ZERO;
+        }
+    }
+}
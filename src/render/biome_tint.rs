@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+use bevy::log::warn;
+use crate::constants::CHUNK_SIZE;
+use crate::generation::biome::{get_biome_data, BiomeType};
+use crate::world::block::{BiomeTint, BlockType};
+use crate::world::chunk_neighborhood::ChunkNeighborhood;
+
+pub const GRASS_COLORMAP_PATH: &str = "assets/colormap/grass.png";
+pub const FOLIAGE_COLORMAP_PATH: &str = "assets/colormap/foliage.png";
+
+/// Rayon (en blocs) du mélange des couleurs autour de chaque colonne, et pas d'échantillonnage
+const BLEND_RADIUS: i32 = 8;
+const BLEND_STEP: i32 = 4;
+
+/// Couleurs sRGB des trois coins de la colormap par défaut : chaud et humide, chaud et sec, froid
+const GRASS_CORNERS: [[f32; 3]; 3] = [[0.28, 0.71, 0.19], [0.75, 0.72, 0.33], [0.50, 0.71, 0.56]];
+const FOLIAGE_CORNERS: [[f32; 3]; 3] = [[0.10, 0.62, 0.05], [0.68, 0.64, 0.16], [0.38, 0.63, 0.47]];
+
+/// Colormap indexée par la température et l'humidité, comme les colormaps vanilla :
+/// x = (1 - température), y = (1 - humidité * température). Seul le triangle y >= x est utilisé.
+pub struct Colormap {
+    width: usize,
+    height: usize,
+    /// Couleurs sRGB, ligne par ligne
+    pixels: Vec<[f32; 3]>,
+}
+
+impl Colormap {
+    /// Lit la colormap `path` ; si elle est absente, la construit à partir des couleurs de ses trois coins
+    fn load(path: &Path, corners: [[f32; 3]; 3]) -> Self {
+        match image::open(path) {
+            Ok(image) => {
+                let image = image.to_rgb8();
+                Self {
+                    width: image.width() as usize,
+                    height: image.height() as usize,
+                    pixels: image.pixels().map(|p| p.0.map(|c| c as f32 / 255.0)).collect(),
+                }
+            }
+            Err(e) => {
+                if path.exists() {
+                    warn!("Colormap {} illisible ({}), couleurs par défaut utilisées", path.display(), e);
+                }
+                Self::from_corners(corners)
+            }
+        }
+    }
+
+    fn from_corners([humid, dry, cold]: [[f32; 3]; 3]) -> Self {
+        let size = 256;
+        let mut pixels = Vec::with_capacity(size * size);
+        for y in 0..size {
+            for x in 0..size {
+                // Coordonnées barycentriques dans le triangle (0, 0) (0, 255) (255, 255)
+                let (u, v) = (x as f32 / 255.0, y as f32 / 255.0);
+                let weights = [1.0 - v, (v - u).max(0.0), u];
+                let total: f32 = weights.iter().sum();
+                pixels.push(std::array::from_fn(|c| {
+                    (humid[c] * weights[0] + dry[c] * weights[1] + cold[c] * weights[2]) / total
+                }));
+            }
+        }
+        Self { width: size, height: size, pixels }
+    }
+
+    pub fn sample(&self, temperature: f64, humidity: f64) -> [f32; 3] {
+        let temperature = temperature.clamp(0.0, 1.0);
+        let humidity = humidity.clamp(0.0, 1.0) * temperature;
+        let x = ((1.0 - temperature) * (self.width - 1) as f64).round() as usize;
+        let y = ((1.0 - humidity) * (self.height - 1) as f64).round() as usize;
+        self.pixels[y * self.width + x]
+    }
+}
+
+pub struct BiomeColormaps {
+    pub grass: Colormap,
+    pub foliage: Colormap,
+}
+
+static COLORMAPS: OnceLock<BiomeColormaps> = OnceLock::new();
+
+impl BiomeColormaps {
+    /// Colormaps partagées, chargées au premier accès (utilisées par les tâches de maillage)
+    pub fn global() -> &'static BiomeColormaps {
+        COLORMAPS.get_or_init(|| BiomeColormaps {
+            grass: Colormap::load(Path::new(GRASS_COLORMAP_PATH), GRASS_CORNERS),
+            foliage: Colormap::load(Path::new(FOLIAGE_COLORMAP_PATH), FOLIAGE_CORNERS),
+        })
+    }
+}
+
+/// Couleur sRGB d'une teinte dans un biome, sans mélange avec les biomes voisins
+pub fn biome_color(biome: BiomeType, tint: BiomeTint) -> [f32; 3] {
+    let data = get_biome_data(biome);
+    let colormaps = BiomeColormaps::global();
+    match tint {
+        BiomeTint::Grass => colormaps.grass.sample(data.temperature, data.humidity),
+        BiomeTint::Foliage => colormaps.foliage.sample(data.temperature, data.humidity),
+        BiomeTint::Water => data.water_color,
+    }
+}
+
+/// Couleurs des biomes aux coins des colonnes d'un chunk (17 x 17 coins), chacune mélangée
+/// avec celles des colonnes voisines pour que les frontières de biomes soient progressives
+pub struct BiomeTints {
+    colors: Vec<[[f32; 3]; 3]>,
+    /// Côté des cases du maillage, en blocs (voir `ChunkNeighborhood::scale`)
+    scale: f32,
+}
+
+impl BiomeTints {
+    /// Tant que la carte des biomes n'est pas prête, tout le chunk prend les couleurs de la plaine
+    pub fn for_chunk(neighborhood: &ChunkNeighborhood) -> Self {
+        let mut cache: HashMap<BiomeType, [[f32; 3]; 3]> = HashMap::new();
+        let mut colors_at = |x: i32, z: i32| {
+            let biome = neighborhood.get_biome(x, z).unwrap_or(BiomeType::Plain);
+            *cache.entry(biome).or_insert_with(|| BiomeTint::ALL.map(|tint| biome_color(biome, tint)))
+        };
+
+        let corners = CHUNK_SIZE + 1;
+        let mut colors = Vec::with_capacity(corners * corners);
+        for z in 0..corners as i32 {
+            for x in 0..corners as i32 {
+                let mut sum = [[0.0f32; 3]; 3];
+                let mut count = 0.0;
+                for dz in (-BLEND_RADIUS..=BLEND_RADIUS).step_by(BLEND_STEP as usize) {
+                    for dx in (-BLEND_RADIUS..=BLEND_RADIUS).step_by(BLEND_STEP as usize) {
+                        let sample = colors_at(x + dx, z + dz);
+                        for (total, color) in sum.iter_mut().zip(sample) {
+                            for c in 0..3 {
+                                total[c] += color[c];
+                            }
+                        }
+                        count += 1.0;
+                    }
+                }
+                colors.push(sum.map(|color| color.map(|c| c / count)));
+            }
+        }
+        Self { colors, scale: neighborhood.scale() as f32 }
+    }
+
+    /// Couleur d'une teinte au coin de colonne le plus proche de (x, z), en coordonnées du maillage
+    /// locales au chunk (en cases, pour un maillage en LOD)
+    pub fn color_at(&self, x: f32, z: f32, tint: BiomeTint) -> [f32; 3] {
+        let corner = |v: f32| ((v * self.scale).round().max(0.0) as usize).min(CHUNK_SIZE);
+        self.colors[corner(z) * (CHUNK_SIZE + 1) + corner(x)][tint.index()]
+    }
+
+    /// Teinte finale d'un sommet : celle du bloc, multipliée par la couleur du biome s'il en a une
+    pub fn vertex_tint(&self, block: BlockType, biome_tint: Option<BiomeTint>, x: f32, z: f32) -> [f32; 4] {
+        let [r, g, b, a] = block.definition().tint;
+        match biome_tint {
+            Some(tint) => {
+                let [br, bg, bb] = self.color_at(x, z, tint);
+                [r * br, g * bg, b * bb, a]
+            }
+            None => [r, g, b, a],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use crate::generation::generate_biome_map::BiomeMap;
+    use crate::world::chunk::Chunk;
+    use crate::world::load_save_chunk::WorldData;
+    use crate::world::world_config::WorldConfig;
+
+    fn assert_close(a: [f32; 3], b: [f32; 3]) {
+        assert!(a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-4), "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn colormaps_are_shipped() {
+        for path in [GRASS_COLORMAP_PATH, FOLIAGE_COLORMAP_PATH] {
+            let image = image::open(path).unwrap_or_else(|e| panic!("{} : {}", path, e));
+            assert_eq!((image.width(), image.height()), (256, 256));
+        }
+    }
+
+    #[test]
+    fn colors_blend_across_a_biome_border_without_seam() {
+        // Plaine partout, montagne dans la case qui commence au bloc x = 256
+        let mut biome_map = BiomeMap::new();
+        biome_map.set_biome(256, 0, BiomeType::Mountain);
+        let biome_map = Arc::new(biome_map);
+
+        let mut world = WorldData::new(Arc::new(WorldConfig::default()));
+        for x in [15, 16] {
+            world.chunks_loaded.insert((x, 2), Arc::new(Chunk::new(x, 2)));
+        }
+        let tints_of = |x: i32| BiomeTints::for_chunk(&ChunkNeighborhood::new(&world, x, 2, Some(biome_map.clone())).unwrap());
+        let (west, east) = (tints_of(15), tints_of(16));
+
+        let plain = biome_color(BiomeType::Plain, BiomeTint::Grass);
+        let mountain = biome_color(BiomeType::Mountain, BiomeTint::Grass);
+        let mix = |t: f32| std::array::from_fn(|c| plain[c] * (1.0 - t) + mountain[c] * t);
+
+        // Loin de la frontière : couleur pure ; sur la frontière (x = 256), 3 échantillons sur 5 en montagne
+        assert_close(west.color_at(0.0, 8.0, BiomeTint::Grass), plain);
+        assert_close(west.color_at(16.0, 8.0, BiomeTint::Grass), mix(0.6));
+        assert_close(east.color_at(16.0, 8.0, BiomeTint::Grass), mountain);
+        // Les deux chunks donnent la même couleur au bord commun
+        assert_close(east.color_at(0.0, 8.0, BiomeTint::Grass), west.color_at(16.0, 8.0, BiomeTint::Grass));
+        // Et la couleur progresse d'un coin à l'autre sans saut
+        assert_close(west.color_at(12.0, 8.0, BiomeTint::Grass), mix(0.4));
+        assert_close(east.color_at(4.0, 8.0, BiomeTint::Grass), mix(0.8));
+    }
+}
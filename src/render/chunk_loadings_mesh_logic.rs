@@ -1,10 +1,7 @@
 use crate::world::chunk::Chunk;
 use crate::texture::TextureAtlasMaterial;
-use bevy_rapier3d::prelude::TriMeshFlags;
-use bevy_rapier3d::prelude::ComputedColliderShape;
-use bevy_rapier3d::prelude::Collider;
 use bevy::prelude::*;
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::error::Error;
 use std::sync::{Arc, Mutex};
 use std::task;
@@ -20,9 +17,22 @@ use bevy_rapier3d::na::DimAdd;
 use futures::channel::mpsc::{UnboundedReceiver, UnboundedSender};
 use futures::FutureExt;
 use crate::player::Player;
-use crate::constants::{CHUNK_SIZE, SECTION_HEIGHT, VIEW_DISTANCE, WORLD_HEIGHT};
-use crate::render::generate_mesh_chunk::generate_mesh_from_chunk;
+use crate::constants::{CHUNK_SIZE, SECTION_HEIGHT};
+use crate::render::biome_tint::BiomeTints;
+use crate::render::generate_mesh_chunk::{generate_mesh_from_chunk, generate_section_mesh, LayerMesh, SectionMesh, PACKED_VERTEX_SIZE};
+use crate::render::voxel_material::VoxelMaterial;
+use crate::render::lod::{neighbor_lods, neighborhood_for_lod, ChunkLods, LodSettings};
+use crate::world::chunk_loadings_logic::PlayerChunk;
+use crate::generation::chunk_generation_logic::BiomeMapArc;
+use crate::world::chunk_neighborhood::ChunkNeighborhood;
+use crate::world::block::BlockType;
+use crate::world::chunk_queue::{ChunkJobKind, ChunkWorkQueue};
+use crate::world::chunk_state::{ChunkStates, ChunkStatus};
 use crate::world::load_save_chunk::{ToLoadChunkEvent, WorldData};
+use crate::world::world_config::WorldConfig;
+
+/// Nombre maximal de chunks maillés en parallèle ; les autres attendent dans `ChunkWorkQueue`
+const MAX_MESH_TASKS: usize = 8;
 
 #[derive(Event,Clone)]
 pub struct ChunkToUpdateEvent {
@@ -30,9 +40,97 @@ pub struct ChunkToUpdateEvent {
     pub z: i32,
 }
 
+/// Demande de remaillage d'une seule section du chunk (x, z)
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SectionToUpdateEvent {
+    pub x: i32,
+    pub z: i32,
+    pub section_y: i8,
+}
+
+impl SectionToUpdateEvent {
+    /// Section qui contient le bloc (wx, wy, wz)
+    pub fn containing(wx: i32, wy: i32, wz: i32) -> Self {
+        SectionToUpdateEvent {
+            x: wx.div_euclid(CHUNK_SIZE as i32),
+            z: wz.div_euclid(CHUNK_SIZE as i32),
+            section_y: wy.div_euclid(SECTION_HEIGHT as i32) as i8,
+        }
+    }
+
+    /// Sections à remailler après la modification du bloc (wx, wy, wz) : celles des 27 blocs autour
+    /// de lui, car les faces voisines en dépendent (faces cachées, occlusion ambiante des diagonales)
+    pub fn around_block(wx: i32, wy: i32, wz: i32) -> HashSet<Self> {
+        let mut events = HashSet::new();
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    events.insert(Self::containing(wx + dx, wy + dy, wz + dz));
+                }
+            }
+        }
+        events
+    }
+
+    pub fn in_world(&self, config: &WorldConfig) -> bool {
+        let min_section = config.min_section() as i32;
+        (min_section..min_section + config.section_count() as i32).contains(&(self.section_y as i32))
+    }
+}
+
+/// Pose d'un bloc dans le monde : met à jour le chunk et sa lumière, puis remaille les sections touchées
+#[derive(Event, Clone, Copy, Debug)]
+pub struct BlockChangeEvent {
+    pub position: IVec3,
+    pub block: BlockType,
+}
+
+/// Clé d'une section dans `WorldData::chunks_sections_meshes` (indice depuis la section la plus basse du monde)
+pub fn section_key(x: i32, z: i32, section_y: i8, min_section: i8) -> (i32, i32, i32) {
+    (x, z, section_y as i32 - min_section as i32)
+}
+
+/// Chaque tâche porte la version qu'elle a réservée pour ses sections. Une tâche de chunk et une tâche
+/// de section peuvent mailler la même section : seul le résultat de la dernière demandée est affiché.
 #[derive(Resource,Default)]
 pub struct ChunkMeshTasks {
-    tasks: HashMap<(i32, i32), Task<Vec<(Mesh, Mesh, Transform)>>>,
+    tasks: HashMap<(i32, i32), (u64, Task<Vec<SectionMesh>>)>,
+    /// Remaillages d'une seule section. Une nouvelle demande remplace (et annule) la tâche en cours.
+    section_tasks: HashMap<(i32, i32, i8), (u64, Task<Option<SectionMesh>>)>,
+    next_version: u64,
+    /// Dernière version demandée pour chaque section dont le maillage n'est pas encore affiché
+    requested: HashMap<(i32, i32, i8), u64>,
+}
+
+impl ChunkMeshTasks {
+    /// Annule (en les abandonnant) les maillages des chunks pour lesquels `keep` est faux.
+    /// Retourne le nombre de tâches annulées.
+    pub fn cancel_outside(&mut self, keep: impl Fn(i32, i32) -> bool) -> usize {
+        let before = self.tasks.len() + self.section_tasks.len();
+        self.tasks.retain(|&(x, z), _| keep(x, z));
+        self.section_tasks.retain(|&(x, z, _), _| keep(x, z));
+        self.requested.retain(|&(x, z, _), _| keep(x, z));
+        before - self.tasks.len() - self.section_tasks.len()
+    }
+
+    /// Réserve une nouvelle version pour les sections `section_ys` du chunk (x, z) :
+    /// les résultats des tâches lancées avant pour ces sections seront ignorés
+    fn claim(&mut self, x: i32, z: i32, section_ys: impl IntoIterator<Item = i8>) -> u64 {
+        self.next_version += 1;
+        for section_y in section_ys {
+            self.requested.insert((x, z, section_y), self.next_version);
+        }
+        self.next_version
+    }
+}
+
+/// Le résultat en `version` de la section `key` est-il le dernier demandé ? Si oui, la demande est soldée.
+fn take_if_current(requested: &mut HashMap<(i32, i32, i8), u64>, key: (i32, i32, i8), version: u64) -> bool {
+    if requested.get(&key) != Some(&version) {
+        return false;
+    }
+    requested.remove(&key);
+    true
 }
 
 pub struct GenerateMeshChunksPlugin;
@@ -40,115 +138,276 @@ pub struct GenerateMeshChunksPlugin;
 impl Plugin for GenerateMeshChunksPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<ChunkToUpdateEvent>();
+        app.add_event::<SectionToUpdateEvent>();
+        app.add_event::<BlockChangeEvent>();
         app.init_resource::<ChunkMeshTasks>();
+        app.add_systems(Update, (apply_block_changes, queue_section_mesh_tasks).chain());
         app.add_systems(Update, queue_chunk_mesh_tasks);
-        app.add_systems(Update, poll_chunk_tasks);
+        app.add_systems(Update, (poll_chunk_tasks, poll_section_tasks));
+        app.add_systems(Update, report_mesh_memory);
 
     }
 }
 
 fn queue_chunk_mesh_tasks(
-    atlas_material: Res<TextureAtlasMaterial>,
     world_data: Res<WorldData>,
+    biome_map: Option<Res<BiomeMapArc>>,
+    player_chunk: Res<PlayerChunk>,
+    lod_settings: Res<LodSettings>,
+    mut chunk_lods: ResMut<ChunkLods>,
     mut load_events: EventReader<ChunkToUpdateEvent>,
     mut chunk_tasks: ResMut<ChunkMeshTasks>,
+    mut work_queue: ResMut<ChunkWorkQueue>,
+    mut states: ResMut<ChunkStates>,
+    cameras: Query<&GlobalTransform, With<Camera3d>>,
 ) {
+    for event in load_events.read() {
+        work_queue.push(event.x, event.z, ChunkJobKind::Mesh);
+    }
+
     let thread_pool = IoTaskPool::get();
+    // Position de la caméra, pour trier les quads translucides
+    let viewer = cameras.iter().next().map_or(Vec3::ZERO, |camera| camera.translation());
 
-    for event in load_events.read() {
-        let x = event.x;
-        let z = event.z;
+    // Le voisinage est pris au lancement de la tâche : il contient les voisins arrivés entre-temps
+    let mut not_loaded_yet = Vec::new();
+    while chunk_tasks.tasks.len() < MAX_MESH_TASKS {
+        let Some(job) = work_queue.pop(ChunkJobKind::Mesh) else {
+            break;
+        };
+        let (x, z) = (job.x, job.z);
 
-        if let Some(chunk_data) = world_data.chunks_loaded.get(&(x, z)) {
-                let chunk_data = chunk_data.clone();
-                let world_data = world_data.clone();
-                let atlas_material = atlas_material.clone();
+        let biome_map = biome_map.as_ref().map(|b| b.0.clone());
+        if let Some(neighborhood) = ChunkNeighborhood::new(&world_data, x, z, biome_map) {
+            let lod = lod_settings.lod_for_chunk(player_chunk.current_chunk, x, z);
+            let neighbor_lods = neighbor_lods(x, z, |x, z| lod_settings.lod_for_chunk(player_chunk.current_chunk, x, z));
+            chunk_lods.levels.insert((x, z), lod);
 
-                let task = thread_pool.spawn(async move {
-                    //generate_chunk_sections_mesh_async(chunk_data, world_data, atlas_material).await
-                    generate_mesh_from_chunk(&chunk_data, &atlas_material).await
-                });
+            let task = thread_pool.spawn(async move {
+                let neighborhood = neighborhood_for_lod(neighborhood, lod, neighbor_lods);
+                generate_mesh_from_chunk(&neighborhood, viewer).await
+            });
 
-                chunk_tasks.tasks.insert((x, z), task);
-            }
+            let config = &world_data.config;
+            let min_section = config.min_section();
+            let version = chunk_tasks.claim(x, z, (0..config.section_count()).map(|i| min_section + i as i8));
+            chunk_tasks.tasks.insert((x, z), (version, task));
+            states.set(x, z, ChunkStatus::Meshing);
+        } else if matches!(states.status(x, z), Some(ChunkStatus::Requested | ChunkStatus::Loading | ChunkStatus::Generating)) {
+            // Blocs pas encore arrivés : le travail attend son tour, remis dans la file après cette passe
+            not_loaded_yet.push(job);
+        }
+    }
+
+    for job in not_loaded_yet {
+        work_queue.push(job.x, job.z, ChunkJobKind::Mesh);
+    }
+}
+
+fn apply_block_changes(
+    mut block_changes: EventReader<BlockChangeEvent>,
+    mut world_data: ResMut<WorldData>,
+    mut states: ResMut<ChunkStates>,
+    mut section_updates: EventWriter<SectionToUpdateEvent>,
+) {
+    let mut sections = HashSet::new();
+    for change in block_changes.read() {
+        let IVec3 { x, y, z } = change.position;
+        let Some(lit_sections) = world_data.set_block(x, y, z, change.block) else {
+            continue;
+        };
+        states.mark_modified(x.div_euclid(CHUNK_SIZE as i32), z.div_euclid(CHUNK_SIZE as i32));
+        sections.extend(SectionToUpdateEvent::around_block(x, y, z));
+        // La lumière est cuite dans les sommets : les sections où elle a changé sont remaillées aussi
+        sections.extend(lit_sections.into_iter().map(|(x, z, section_y)| SectionToUpdateEvent { x, z, section_y }));
+    }
+    section_updates.write_batch(sections.into_iter().filter(|section| section.in_world(&world_data.config)));
+}
+
+fn queue_section_mesh_tasks(
+    world_data: Res<WorldData>,
+    biome_map: Option<Res<BiomeMapArc>>,
+    chunk_lods: Res<ChunkLods>,
+    mut section_events: EventReader<SectionToUpdateEvent>,
+    mut chunk_tasks: ResMut<ChunkMeshTasks>,
+    cameras: Query<&GlobalTransform, With<Camera3d>>,
+) {
+    let thread_pool = IoTaskPool::get();
+    let viewer = cameras.iter().next().map_or(Vec3::ZERO, |camera| camera.translation());
+
+    for event in section_events.read() {
+        let biome_map = biome_map.as_ref().map(|b| b.0.clone());
+        let Some(neighborhood) = ChunkNeighborhood::new(&world_data, event.x, event.z, biome_map) else {
+            continue;
+        };
+        // Même niveau de détail que le reste du chunk
+        let lod_of = |x: i32, z: i32| chunk_lods.levels.get(&(x, z)).copied().unwrap_or_default();
+        let lod = lod_of(event.x, event.z);
+        let neighbor_lods = neighbor_lods(event.x, event.z, lod_of);
+        let section_y = event.section_y;
+
+        let task = thread_pool.spawn(async move {
+            let neighborhood = neighborhood_for_lod(neighborhood, lod, neighbor_lods);
+            let section = neighborhood.center().section(section_y)?;
+            Some(generate_section_mesh(&neighborhood, section, &BiomeTints::for_chunk(&neighborhood), viewer))
+        });
+        let version = chunk_tasks.claim(event.x, event.z, [section_y]);
+        chunk_tasks.section_tasks.insert((event.x, event.z, section_y), (version, task));
+    }
+}
+
+/// Crée une entité par couche de rendu non vide, avec son AABB locale calculée sur les quads réels.
+/// Elle sert au frustum culling et est aussi posée en composant sur l'entité.
+fn spawn_section_entities(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &TextureAtlasMaterial,
+    section_mesh: SectionMesh,
+) -> Vec<(Entity, Aabb)> {
+    let SectionMesh { layers, transform, .. } = section_mesh;
+
+    layers.into_iter().map(|LayerMesh { layer, mesh, aabb }| {
+        let entity = commands.spawn((
+            Mesh3d(meshes.add(mesh)),
+            MeshMaterial3d(materials.handle_for(layer).clone()),
+            transform,
+            GlobalTransform::default(),
+            aabb,
+        ));
+        (entity.id(), aabb)
+    }).collect()
+}
+
+/// Met `entities` à la place des entités de la section `key`. Les anciennes entités
+/// sont supprimées par le même lot de commandes que celui qui crée les nouvelles :
+/// la section n'est jamais affichée en double ni absente. Leurs meshes sont libérés aussitôt.
+fn replace_section_entities(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    mesh_handles: &Query<&Mesh3d>,
+    world_data: &mut WorldData,
+    key: (i32, i32, i32),
+    entities: Vec<(Entity, Aabb)>,
+) {
+    let previous = world_data.chunks_sections_meshes.insert(key, entities);
+    for (entity, _) in previous.into_iter().flatten() {
+        if let Ok(mesh) = mesh_handles.get(entity) {
+            meshes.remove(&mesh.0);
+        }
+        commands.entity(entity).despawn();
+    }
+}
+
+fn poll_section_tasks(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mesh_handles: Query<&Mesh3d>,
+    materials: Res<TextureAtlasMaterial>,
+    mut chunk_tasks: ResMut<ChunkMeshTasks>,
+    mut world_data: ResMut<WorldData>,
+) {
+    let mut completed = Vec::new();
+    let min_section = world_data.config.min_section();
+    let ChunkMeshTasks { section_tasks, requested, .. } = &mut *chunk_tasks;
+
+    for (&(x, z, section_y), (version, task)) in section_tasks.iter_mut() {
+        let Some(section_mesh) = future::block_on(future::poll_once(task)) else {
+            continue;
+        };
+        completed.push((x, z, section_y));
+        // Une tâche de chunk lancée après celle-ci a déjà remplacé (ou remplacera) ce maillage
+        if !take_if_current(requested, (x, z, section_y), *version) {
+            continue;
+        }
+        let Some(section_mesh) = section_mesh else {
+            continue;
+        };
+        if !world_data.chunks_loaded.contains_key(&(x, z)) {
+            continue;
+        }
+
+        let entities = spawn_section_entities(&mut commands, &mut meshes, &materials, section_mesh);
+        replace_section_entities(&mut commands, &mut meshes, &mesh_handles, &mut world_data, section_key(x, z, section_y, min_section), entities);
+    }
+
+    for key in completed {
+        chunk_tasks.section_tasks.remove(&key);
     }
 }
 
 fn poll_chunk_tasks(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
+    mesh_handles: Query<&Mesh3d>,
     materials: Res<TextureAtlasMaterial>,
     mut chunk_tasks: ResMut<ChunkMeshTasks>,
-    mut world_data: ResMut<WorldData>
+    mut world_data: ResMut<WorldData>,
+    mut states: ResMut<ChunkStates>,
 ) {
     let mut completed = Vec::new();
+    let min_section = world_data.config.min_section();
+    let ChunkMeshTasks { tasks, requested, .. } = &mut *chunk_tasks;
 
-    for (&coords, task) in chunk_tasks.tasks.iter_mut() {
+    for (&coords, (version, task)) in tasks.iter_mut() {
         if let Some(sections) = future::block_on(future::poll_once(task)) {
-            for (index_section, (opaque_mesh, water_mesh, transform)) in sections.into_iter().enumerate() {
-                let opaque_mesh_handle = meshes.add(opaque_mesh.clone());
-
-                let section_index: i32 = index_section.try_into().unwrap();
-                let chunk_key = (coords.0, coords.1, section_index.try_into().unwrap());
-                let aabb_local = Aabb {
-                    center: Vec3A::new(
-                        CHUNK_SIZE as f32 / 2.0,
-                        WORLD_HEIGHT as f32 / 2.0,
-                        CHUNK_SIZE as f32 / 2.0,
-                    ),
-                    half_extents: Vec3A::new(
-                        CHUNK_SIZE as f32 / 2.0,
-                        WORLD_HEIGHT as f32 / 2.0,
-                        CHUNK_SIZE as f32 / 2.0,
-                    ),
-                };
-
-                // Mesh opaque + collider
-                if let Some(collider) = Collider::from_bevy_mesh(
-                    &opaque_mesh,
-                    &ComputedColliderShape::TriMesh(bevy_rapier3d::geometry::TriMeshFlags::default()),
-                ) {
-                    let entity = commands.spawn((
-                        Mesh3d(opaque_mesh_handle.clone()),
-                        MeshMaterial3d(materials.opaque_handle.clone()),
-                        transform,
-                        GlobalTransform::default(),
-                        collider,
-                    )).id();
-
-                    world_data.chunks_sections_meshes
-                        .entry(chunk_key)
-                        .or_insert_with(Vec::new)
-                        .push((entity, aabb_local));
-                } else {
-                    warn!("Pas de collider généré pour le mesh opaque du chunk {:?}", coords);
-                }
-
-                // Water mesh (pas de collider ici)
-                if let Some(indices) = water_mesh.indices() {
-                    if !indices.is_empty() {
-                        let water_mesh_handle = meshes.add(water_mesh);
-
-                        let entity = commands.spawn((
-                            Mesh3d(water_mesh_handle),
-                            MeshMaterial3d(materials.water_handle.clone()), // transparent
-                            transform,
-                            GlobalTransform::default(),
-                        )).id();
-
-                        world_data.chunks_sections_meshes
-                            .entry(chunk_key)
-                            .or_insert_with(Vec::new)
-                            .push((entity, aabb_local));
-                    }
+            completed.push(coords);
+            // Chunk déchargé pendant le maillage
+            if !states.is(coords.0, coords.1, ChunkStatus::Meshing) {
+                continue;
+            }
+            for section_mesh in sections {
+                // Section remaillée seule depuis le lancement de cette tâche : son résultat est plus récent
+                if !take_if_current(requested, (coords.0, coords.1, section_mesh.section_y), *version) {
+                    continue;
                 }
-
-                completed.push(coords);
+                let chunk_key = section_key(coords.0, coords.1, section_mesh.section_y, min_section);
+                let entities = spawn_section_entities(&mut commands, &mut meshes, &materials, section_mesh);
+                replace_section_entities(&mut commands, &mut meshes, &mesh_handles, &mut world_data, chunk_key, entities);
             }
+            // Sections vides absentes du résultat
+            requested.retain(|&(x, z, _), v| (x, z) != coords || *v != *version);
+            states.set(coords.0, coords.1, ChunkStatus::Ready);
         }
     }
 
     for coords in completed {
         chunk_tasks.tasks.remove(&coords);
     }
-}
\ No newline at end of file
+}
+
+/// Taille par sommet de l'ancien format flottant : position, normale, UV, couche et couleur
+const LEGACY_VERTEX_SIZE: usize = 12 + 12 + 8 + 8 + 16;
+
+/// Affiche régulièrement la mémoire GPU occupée par les meshes des sections,
+/// comparée à ce qu'occuperait l'ancien format de sommets flottants avec indices 32 bits
+fn report_mesh_memory(
+    time: Res<Time>,
+    mut timer: Local<Option<Timer>>,
+    meshes: Res<Assets<Mesh>>,
+    section_meshes: Query<&Mesh3d, With<MeshMaterial3d<VoxelMaterial>>>,
+) {
+    let timer = timer.get_or_insert_with(|| Timer::from_seconds(10.0, TimerMode::Repeating));
+    if !timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let (mut vertices, mut indices, mut packed_bytes) = (0usize, 0usize, 0usize);
+    for mesh in section_meshes.iter().filter_map(|handle| meshes.get(&handle.0)) {
+        vertices += mesh.count_vertices();
+        indices += mesh.indices().map_or(0, |i| i.len());
+        packed_bytes += mesh.get_vertex_buffer_size() + mesh.get_index_buffer_bytes().map_or(0, |b| b.len());
+    }
+    if vertices == 0 {
+        return;
+    }
+
+    let legacy_bytes = vertices * LEGACY_VERTEX_SIZE + indices * 4;
+    info!(
+        "Meshes des sections : {} sommets, {:.2} Mo (format flottant : {:.2} Mo, {} octets/sommet au lieu de {})",
+        vertices,
+        packed_bytes as f64 / (1024.0 * 1024.0),
+        legacy_bytes as f64 / (1024.0 * 1024.0),
+        PACKED_VERTEX_SIZE,
+        LEGACY_VERTEX_SIZE,
+    );
+}
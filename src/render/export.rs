@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use bevy::prelude::*;
+use bevy::tasks::{block_on, IoTaskPool};
+use noise::Perlin;
+use crate::generation::generate_biome_map::BiomeMap;
+use crate::generation::generate_chunk::generate_chunk;
+use crate::generation::generate_height_map::HeightMap;
+use crate::render::biome_tint::BiomeTints;
+use crate::render::generate_mesh_chunk::{generate_model_faces, generate_quads_for_section, quad_corners, split_into_cells};
+use crate::texture::{load_atlas_uv_map, TextureAtlasMaterial};
+use crate::world::block::{BlockRegistry, RenderLayer};
+use crate::world::chunk_loadings_logic::PlayerChunk;
+use crate::world::chunk_neighborhood::ChunkNeighborhood;
+use crate::world::load_save_chunk::{load_chunk, WorldData};
+use crate::world::world_config::WorldConfig;
+
+pub const ATLAS_IMAGE_PATH: &str = "assets/atlas_texture.png";
+pub const EXPORTS_DIR: &str = "exports";
+
+/// Rayon (en chunks) autour du joueur exporté par la touche F8
+const EXPORT_KEY_RADIUS: i32 = 4;
+
+/// Position et taille de chaque texture dans l'atlas, par identifiant de texture
+pub type AtlasUvMap = HashMap<u16, ([f32; 2], [f32; 2])>;
+
+/// Demande d'export des chunks de `min` à `max` (inclus) vers un fichier OBJ
+#[derive(Event, Clone)]
+pub struct ExportRegionEvent {
+    pub min: IVec2,
+    pub max: IVec2,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ExportStats {
+    pub chunks: usize,
+    pub faces: usize,
+}
+
+pub struct WorldExportPlugin;
+
+impl Plugin for WorldExportPlugin {
+    fn build(&self, app: &mut App) {
+        // `--export` : export sans jeu, le processus s'arrête avant la première image
+        let args: Vec<String> = std::env::args().collect();
+        if let Some(result) = export_from_args(&args) {
+            match result {
+                Ok(stats) => {
+                    info!("Export de {} chunks ({} faces) terminé", stats.chunks, stats.faces);
+                    std::process::exit(0);
+                }
+                Err(e) => {
+                    error!("Échec de l'export : {:?}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        app.add_event::<ExportRegionEvent>();
+        app.add_systems(Update, (export_key, handle_export_requests).chain());
+    }
+}
+
+/// F8 : exporte les chunks autour du joueur
+fn export_key(
+    keys: Res<ButtonInput<KeyCode>>,
+    player_chunk: Res<PlayerChunk>,
+    mut export_events: EventWriter<ExportRegionEvent>,
+) {
+    if keys.just_pressed(KeyCode::F8) {
+        let center = player_chunk.current_chunk;
+        export_events.write(ExportRegionEvent {
+            min: center - IVec2::splat(EXPORT_KEY_RADIUS),
+            max: center + IVec2::splat(EXPORT_KEY_RADIUS),
+            path: Path::new(EXPORTS_DIR).join(format!("chunks_{}_{}.obj", center.x, center.y)),
+        });
+    }
+}
+
+/// Exporte en tâche de fond une copie des chunks chargés (les `Arc` sont partagés, rien n'est recopié)
+fn handle_export_requests(
+    mut export_events: EventReader<ExportRegionEvent>,
+    world_data: Res<WorldData>,
+    atlas_material: Option<Res<TextureAtlasMaterial>>,
+) {
+    let Some(atlas_material) = atlas_material else {
+        return;
+    };
+
+    for event in export_events.read() {
+        let chunks_loaded = world_data.chunks_loaded.iter()
+            .filter(|((x, z), _)| {
+                (event.min.x - 1..=event.max.x + 1).contains(x) && (event.min.y - 1..=event.max.y + 1).contains(z)
+            })
+            .map(|(pos, chunk)| (*pos, chunk.clone()))
+            .collect();
+        let snapshot = WorldData { chunks_loaded, ..WorldData::new(world_data.config.clone()) };
+        let uv_map = atlas_material.uv_map.clone();
+        let event = event.clone();
+
+        IoTaskPool::get().spawn(async move {
+            match export_obj(&snapshot, event.min, event.max, &uv_map, &event.path) {
+                Ok(stats) => info!("Export de {} chunks ({} faces) vers {}", stats.chunks, stats.faces, event.path.display()),
+                Err(e) => error!("Échec de l'export vers {} : {:?}", event.path.display(), e),
+            }
+        }).detach();
+    }
+}
+
+/// Export sans jeu ni GPU : charge les chunks depuis les fichiers de région (ou les génère
+/// comme en jeu s'ils n'ont jamais été sauvegardés), puis les exporte.
+pub fn export_headless(min: IVec2, max: IVec2, path: &Path) -> anyhow::Result<ExportStats> {
+    let mut biome_map = BiomeMap::new();
+    biome_map.generate();
+    let config = Arc::new(WorldConfig::load());
+    let height_map = HeightMap::new(config.sea_level);
+    let perlin = Perlin::new(0);
+
+    // Une bordure d'un chunk en plus, pour cacher et éclairer les faces du bord comme en jeu
+    let mut world = WorldData::new(config.clone());
+    for x in min.x - 1..=max.x + 1 {
+        for z in min.y - 1..=max.y + 1 {
+            let mut chunk = block_on(load_chunk(x, z))?;
+            if chunk.sections.is_empty() {
+                chunk = block_on(generate_chunk(x, z, &config, &perlin, &biome_map, &height_map));
+            }
+            world.chunks_loaded.insert((x, z), Arc::new(chunk));
+            world.stitch_light_borders(x, z);
+        }
+    }
+
+    let uv_map = load_atlas_uv_map(BlockRegistry::global());
+    export_obj(&world, min, max, &uv_map, path)
+}
+
+/// Point d'entrée en ligne de commande : `--export <x0> <z0> <x1> <z1> <fichier.obj>`, lu au montage
+/// de `WorldExportPlugin`. Peut aussi être appelé en tête de `main`, avant de créer l'`App`, pour
+/// n'initialiser ni fenêtre ni GPU. Retourne None si les arguments ne demandent pas d'export.
+pub fn export_from_args(args: &[String]) -> Option<anyhow::Result<ExportStats>> {
+    let position = args.iter().position(|arg| arg == "--export")?;
+    let parameters = &args[position + 1..];
+
+    let parse = || -> anyhow::Result<ExportStats> {
+        let [x0, z0, x1, z1, path] = parameters else {
+            anyhow::bail!("Usage : --export <x0> <z0> <x1> <z1> <fichier.obj>");
+        };
+        let (x0, z0, x1, z1): (i32, i32, i32, i32) = (x0.parse()?, z0.parse()?, x1.parse()?, z1.parse()?);
+        export_headless(
+            IVec2::new(x0.min(x1), z0.min(z1)),
+            IVec2::new(x0.max(x1), z0.max(z1)),
+            Path::new(path),
+        )
+    };
+    Some(parse())
+}
+
+/// Écrit les chunks chargés de `min` à `max` (inclus) dans un fichier OBJ, avec son fichier MTL
+/// et une copie de l'atlas à côté. Les quads fusionnés sont redécoupés par bloc, car une
+/// texture de l'atlas ne peut pas se répéter. Un matériau par couche de rendu (voir `material_name`) :
+/// `opaque`, `cutout` (feuilles, plantes) et `water` (eau, verre, glace). La teinte des blocs est
+/// écrite en couleur de sommet (extension lue par Blender).
+pub fn export_obj(world: &WorldData, min: IVec2, max: IVec2, uv_map: &AtlasUvMap, path: &Path) -> anyhow::Result<ExportStats> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mtl_path = path.with_extension("mtl");
+    let atlas_name = Path::new(ATLAS_IMAGE_PATH).file_name().expect("Nom de fichier de l'atlas");
+    write_mtl(&mtl_path, &atlas_name.to_string_lossy())?;
+    if let Some(parent) = path.parent() {
+        if Path::new(ATLAS_IMAGE_PATH).exists() {
+            fs::copy(ATLAS_IMAGE_PATH, parent.join(atlas_name))?;
+        }
+    }
+
+    // Faces par matériau, dans l'ordre des couches de rendu
+    let mut groups: [Vec<ExportFace>; 3] = Default::default();
+    let mut stats = ExportStats::default();
+
+    for x in min.x..=max.x {
+        for z in min.y..=max.y {
+            let Some(neighborhood) = ChunkNeighborhood::new(world, x, z, None) else {
+                continue;
+            };
+            stats.chunks += 1;
+            let tints = BiomeTints::for_chunk(&neighborhood);
+            let origin = Vec3::new((x * 16) as f32, 0.0, (z * 16) as f32);
+
+            for section in &neighborhood.center().sections {
+                let section_origin = origin + Vec3::Y * (section.y as i32 * 16) as f32;
+                let quads = generate_quads_for_section(&neighborhood, section);
+                let model_faces = generate_model_faces(&neighborhood, section);
+
+                for layer in RenderLayer::ALL {
+                    let group = &mut groups[layer.index()];
+
+                    for cell in quads[layer.index()].iter().flat_map(split_into_cells) {
+                        let corners = quad_corners(&cell).map(|c| Vec3::new(c[0] as f32, c[1] as f32, c[2] as f32));
+                        let biome_tint = cell.type_blocks.definition().biome_tint_for(cell.direction);
+                        group.push(ExportFace {
+                            positions: corners.map(|c| section_origin + c),
+                            uvs: [Vec2::new(0.0, 1.0), Vec2::new(1.0, 1.0), Vec2::new(1.0, 0.0), Vec2::new(0.0, 0.0)],
+                            texture: cell.texture,
+                            tint: tints.vertex_tint(cell.type_blocks, biome_tint, corners[0].x, corners[0].z),
+                        });
+                    }
+                    for face in &model_faces[layer.index()] {
+                        let biome_tint = face.block.definition().biome_tint;
+                        group.push(ExportFace {
+                            positions: face.positions.map(|p| section_origin + p),
+                            uvs: face.uvs,
+                            texture: face.texture,
+                            tint: tints.vertex_tint(face.block, biome_tint, face.positions[0].x, face.positions[0].z),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    let mut out = BufWriter::new(File::create(path)?);
+    writeln!(out, "# Export des chunks ({}, {}) à ({}, {})", min.x, min.y, max.x, max.y)?;
+    writeln!(out, "mtllib {}", mtl_path.file_name().expect("Nom du fichier MTL").to_string_lossy())?;
+
+    let mut vertex_count = 0;
+    for layer in RenderLayer::ALL {
+        let (material, faces) = (material_name(layer), &groups[layer.index()]);
+        if faces.is_empty() {
+            continue;
+        }
+        writeln!(out, "o {}", material)?;
+        writeln!(out, "usemtl {}", material)?;
+        for face in faces {
+            let normal = (face.positions[1] - face.positions[0]).cross(face.positions[3] - face.positions[0]).normalize_or_zero();
+            let (base, size) = uv_map.get(&face.texture).copied().unwrap_or(([0.0, 0.0], [0.0, 0.0]));
+            let [r, g, b, _] = face.tint;
+
+            for (position, uv) in face.positions.iter().zip(face.uvs) {
+                writeln!(out, "v {} {} {} {} {} {}", position.x, position.y, position.z, r, g, b)?;
+                // Les UVs de l'atlas ont l'origine en haut, celles d'OBJ en bas
+                let atlas_u = base[0] + uv.x * size[0];
+                let atlas_v = base[1] + uv.y * size[1];
+                writeln!(out, "vt {} {}", atlas_u, 1.0 - atlas_v)?;
+            }
+            writeln!(out, "vn {} {} {}", normal.x, normal.y, normal.z)?;
+
+            let n = stats.faces + 1;
+            let [a, b, c, d] = [1, 2, 3, 4].map(|i| vertex_count + i);
+            writeln!(out, "f {a}/{a}/{n} {b}/{b}/{n} {c}/{c}/{n} {d}/{d}/{n}")?;
+            vertex_count += 4;
+            stats.faces += 1;
+        }
+    }
+    out.flush()?;
+
+    Ok(stats)
+}
+
+struct ExportFace {
+    positions: [Vec3; 4],
+    uvs: [Vec2; 4],
+    texture: u16,
+    tint: [f32; 4],
+}
+
+fn material_name(layer: RenderLayer) -> &'static str {
+    match layer {
+        RenderLayer::Opaque => "opaque",
+        RenderLayer::Cutout => "cutout",
+        RenderLayer::Translucent => "water",
+    }
+}
+
+/// Un matériau par couche de rendu, avec la transparence de la couche
+fn write_mtl(path: &Path, atlas_name: &str) -> anyhow::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    for layer in RenderLayer::ALL {
+        writeln!(out, "newmtl {}", material_name(layer))?;
+        writeln!(out, "Kd 1 1 1")?;
+        writeln!(out, "map_Kd {}", atlas_name)?;
+        match layer {
+            RenderLayer::Opaque => {}
+            // Le canal alpha de l'atlas découpe les blocs de la couche
+            RenderLayer::Cutout => writeln!(out, "map_d {}", atlas_name)?,
+            RenderLayer::Translucent => writeln!(out, "d 0.8")?,
+        }
+        writeln!(out)?;
+    }
+    out.flush()?;
+    Ok(())
+}
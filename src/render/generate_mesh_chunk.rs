@@ -1,10 +1,29 @@
-use std::collections::HashMap;
 use bevy::asset::RenderAssetUsages;
 use bevy::prelude::*;
-use bevy::render::mesh::{Indices, PrimitiveTopology};
-use crate::world::block::BlockType;
-use crate::texture::TextureAtlasMaterial;
+use bevy::render::mesh::{Indices, MeshVertexAttribute, PrimitiveTopology};
+use bevy::render::primitives::Aabb;
+use bevy::render::render_resource::VertexFormat;
+use crate::render::biome_tint::BiomeTints;
+use crate::world::block::{BlockRegistry, BlockType, RenderLayer};
 use crate::world::chunk::{Chunk, ChunkSection};
+use crate::world::chunk_neighborhood::ChunkNeighborhood;
+use crate::world::direction::Direction;
+
+/// Sommet compressé des sections : deux u32 par sommet au lieu de ~56 octets de flottants.
+///
+/// Mot 0 : x (bits 0-4), y (5-9), z (10-14) en coordonnées locales à la section (0..=16),
+/// indice de normale (15-17, ordre de `Direction`), occlusion ambiante (18-19),
+/// lumière du ciel (20-23), lumière des blocs (24-27) et surface de l'eau (28) : le shader
+/// abaisse et anime ces sommets.
+/// Mot 1 : couche du tableau de textures (bits 0-9), teinte sRGB du bloc sur 6 bits
+/// par canal (r 10-15, g 16-21, b 22-27) et opacité sur 4 bits (28-31).
+///
+/// Les UVs ne sont pas stockées : le shader les déduit de la position et de la normale.
+pub const ATTRIBUTE_PACKED_VOXEL: MeshVertexAttribute =
+    MeshVertexAttribute::new("Vertex_PackedVoxel", 0x6a3d_0f01, VertexFormat::Uint32x2);
+
+/// Taille en octets d'un sommet compressé
+pub const PACKED_VERTEX_SIZE: usize = 8;
 
 #[derive(Debug)]
 pub struct Quad {
@@ -15,218 +34,243 @@ pub struct Quad {
     pub height: usize,
     pub direction: Direction,
     pub type_blocks: BlockType,
+    pub texture: u16,
+    /// Occlusion ambiante (0 = très sombre, 3 = aucune) aux coins (u0,v0), (u1,v0), (u1,v1), (u0,v1)
+    pub ao: [u8; 4],
+    /// Lumière du ciel et des blocs de la case devant la face
+    pub sky_light: u8,
+    pub block_light: u8,
+    /// Face d'un fluide au niveau de sa surface (rien au-dessus du même fluide)
+    pub surface: bool,
 }
 
-pub fn quads_to_mesh(quads: &[Quad], uv_map: &HashMap<BlockType, ([f32; 2], [f32; 2])>) -> Mesh {
+/// Face libre d'un modèle de bloc (dalle, plante en croix...), en coordonnées locales à la section
+#[derive(Debug)]
+pub struct ModelFace {
+    pub positions: [Vec3; 4],
+    pub normal: Vec3,
+    pub uvs: [Vec2; 4],
+    pub texture: u16,
+    pub block: BlockType,
+    pub sky_light: u8,
+    pub block_light: u8,
+}
+
+/// Mesh d'une couche de rendu d'une section, avec son AABB locale.
+/// Une couche peut avoir deux meshes : les cubes (sommets compressés) et les modèles.
+pub struct LayerMesh {
+    pub layer: RenderLayer,
+    pub mesh: Mesh,
+    pub aabb: Aabb,
+}
+
+/// Maillage d'une section : un mesh par couche de rendu non vide.
+/// Les meshes compressés n'ont plus d'attribut de position, d'où des AABB
+/// calculées directement à partir des quads. Les collisions sont construites
+/// à part, à partir des blocs (voir `voxel_collision`).
+pub struct SectionMesh {
+    pub section_y: i8,
+    pub layers: Vec<LayerMesh>,
+    pub transform: Transform,
+}
+
+/// Pour chaque sommet émis par `quads_to_mesh`, l'index du coin correspondant dans `Quad::ao`
+fn ao_corner_for_vertices(direction: Direction) -> [usize; 4] {
+    match direction {
+        Direction::Up => [3, 2, 1, 0],
+        Direction::Down | Direction::South | Direction::West => [0, 1, 2, 3],
+        Direction::North | Direction::East => [1, 0, 3, 2],
+    }
+}
+
+/// Les 4 sommets d'un quad en coordonnées locales à la section, dans le sens anti-horaire
+/// vu de l'extérieur de la face
+pub fn quad_corners(quad: &Quad) -> [[u32; 3]; 4] {
+    let (x, y, z) = (quad.x as u32, quad.y as u32, quad.z as u32);
+    let (w, h) = (quad.width as u32, quad.height as u32);
+
+    match quad.direction {
+        // Face supérieure (normale vers +Y), vue du dessus
+        Direction::Up => [[x, y + 1, z + h], [x + w, y + 1, z + h], [x + w, y + 1, z], [x, y + 1, z]],
+        // Face inférieure (normale vers -Y), vue du dessous
+        Direction::Down => [[x, y, z], [x + w, y, z], [x + w, y, z + h], [x, y, z + h]],
+        // Face nord (normale vers -Z), vue de face
+        Direction::North => [[x + w, y, z], [x, y, z], [x, y + h, z], [x + w, y + h, z]],
+        // Face sud (normale vers +Z), vue de derrière
+        Direction::South => [[x, y, z + 1], [x + w, y, z + 1], [x + w, y + h, z + 1], [x, y + h, z + 1]],
+        // Face ouest (normale vers -X), vue de gauche
+        Direction::West => [[x, y, z], [x, y, z + w], [x, y + h, z + w], [x, y + h, z]],
+        // Face est (normale vers +X), vue de droite
+        Direction::East => [[x + 1, y, z + w], [x + 1, y, z], [x + 1, y + h, z], [x + 1, y + h, z + w]],
+    }
+}
+
+/// Indices des deux triangles d'un quad. On coupe le quad selon la diagonale
+/// la plus claire pour éviter l'anisotropie de l'AO.
+fn quad_indices(vertex_ao: [u8; 4]) -> [u32; 6] {
+    if vertex_ao[0] as u32 + vertex_ao[2] as u32 >= vertex_ao[1] as u32 + vertex_ao[3] as u32 {
+        [0, 1, 2, 2, 3, 0]
+    } else {
+        [1, 2, 3, 3, 0, 1]
+    }
+}
+
+fn pack_tint(tint: [f32; 4]) -> u32 {
+    let channel = |value: f32, max: u32| (value.clamp(0.0, 1.0) * max as f32).round() as u32;
+    channel(tint[0], 63) | channel(tint[1], 63) << 6 | channel(tint[2], 63) << 12 | channel(tint[3], 15) << 18
+}
+
+fn pack_vertex(position: [u32; 3], direction: Direction, ao: u8, sky_light: u8, block_light: u8, surface: bool, layer: u16, tint: u32) -> [u32; 2] {
+    let word0 = position[0]
+        | position[1] << 5
+        | position[2] << 10
+        | (direction as u32) << 15
+        | (ao as u32 & 0x3) << 18
+        | (sky_light as u32 & 0xF) << 20
+        | (block_light as u32 & 0xF) << 24
+        | (surface as u32) << 28;
+    [word0, (layer as u32 & 0x3FF) | tint << 10]
+}
+
+pub fn quads_to_mesh(quads: &[Quad], tints: &BiomeTints) -> Mesh {
     let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default());
 
-    let mut positions = Vec::new();
-    let mut normals = Vec::new();
-    let mut uvs = Vec::new();
-    let mut indices = Vec::new();
-    let mut vertex_offset = 0;
+    let mut vertices = Vec::with_capacity(quads.len() * 4);
+    let mut indices = Vec::with_capacity(quads.len() * 6);
 
     for quad in quads {
-        let (base_uv, size_uv) = uv_map
-            .get(&quad.type_blocks)
-            .unwrap_or(&([0.0, 0.0], [1.0, 1.0]));
-
-        // Calculer les UVs basés sur les coordonnées mondiales
-        // La texture se répète tous les 10 blocs
-        let repeat_frequency = 8.0;
-
-        let (quad_positions, quad_normals) = match quad.direction {
-            Direction::Up => {
-                // Face supérieure (normale vers +Y)
-                // Vue du dessus, sens anti-horaire
-                let y = quad.y as f32 + 1.0;
-                (
-                    vec![
-                        [quad.x as f32, y, quad.z as f32 + quad.height as f32],             // 0: coin haut-gauche
-                        [quad.x as f32 + quad.width as f32, y, quad.z as f32 + quad.height as f32], // 1: coin haut-droite
-                        [quad.x as f32 + quad.width as f32, y, quad.z as f32],              // 2: coin bas-droite
-                        [quad.x as f32, y, quad.z as f32],                                    // 3: coin bas-gauche
-                    ],
-                    vec![[0.0, 1.0, 0.0]; 4],
-                )
-            },
-            Direction::Down => {
-                // Face inférieure (normale vers -Y)
-                // Vue du dessous, sens anti-horaire
-                let y = quad.y as f32;
-                (
-                    vec![
-                        [quad.x as f32, y, quad.z as f32],                                    // 0: coin bas-gauche
-                        [quad.x as f32 + quad.width as f32, y, quad.z as f32],              // 1: coin bas-droite
-                        [quad.x as f32 + quad.width as f32, y, quad.z as f32 + quad.height as f32], // 2: coin haut-droite
-                        [quad.x as f32, y, quad.z as f32 + quad.height as f32],             // 3: coin haut-gauche
-                    ],
-                    vec![[0.0, -1.0, 0.0]; 4],
-                )
-            },
-            Direction::North => {
-                // Face nord (normale vers -Z)
-                // Vue de face, sens anti-horaire
-                let z = quad.z as f32;
-                (
-                    vec![
-                        [quad.x as f32 + quad.width as f32, quad.y as f32, z],              // 0: coin bas-droite
-                        [quad.x as f32, quad.y as f32, z],                                    // 1: coin bas-gauche
-                        [quad.x as f32, quad.y as f32 + quad.height as f32, z],             // 2: coin haut-gauche
-                        [quad.x as f32 + quad.width as f32, quad.y as f32 + quad.height as f32, z], // 3: coin haut-droite
-                    ],
-                    vec![[0.0, 0.0, -1.0]; 4],
-                )
-            },
-            Direction::South => {
-                // Face sud (normale vers +Z)
-                // Vue de derrière, sens anti-horaire
-                let z = quad.z as f32 + 1.0;
-                (
-                    vec![
-                        [quad.x as f32, quad.y as f32, z],                                    // 0: coin bas-gauche
-                        [quad.x as f32 + quad.width as f32, quad.y as f32, z],              // 1: coin bas-droite
-                        [quad.x as f32 + quad.width as f32, quad.y as f32 + quad.height as f32, z], // 2: coin haut-droite
-                        [quad.x as f32, quad.y as f32 + quad.height as f32, z],             // 3: coin haut-gauche
-                    ],
-                    vec![[0.0, 0.0, 1.0]; 4],
-                )
-            },
-            Direction::West => {
-                // Face ouest (normale vers -X)
-                // Vue de gauche, sens anti-horaire
-                let x = quad.x as f32;
-                (
-                    vec![
-                        [x, quad.y as f32, quad.z as f32],                                    // 0: coin bas-gauche
-                        [x, quad.y as f32, quad.z as f32 + quad.width as f32],              // 1: coin bas-droite
-                        [x, quad.y as f32 + quad.height as f32, quad.z as f32 + quad.width as f32], // 2: coin haut-droite
-                        [x, quad.y as f32 + quad.height as f32, quad.z as f32],             // 3: coin haut-gauche
-                    ],
-                    vec![[-1.0, 0.0, 0.0]; 4],
-                )
-            },
-            Direction::East => {
-                // Face est (normale vers +X)
-                // Vue de droite, sens anti-horaire
-                let x = quad.x as f32 + 1.0;
-                (
-                    vec![
-                        [x, quad.y as f32, quad.z as f32 + quad.width as f32],              // 0: coin bas-droite
-                        [x, quad.y as f32, quad.z as f32],                                    // 1: coin bas-gauche
-                        [x, quad.y as f32 + quad.height as f32, quad.z as f32],             // 2: coin haut-gauche
-                        [x, quad.y as f32 + quad.height as f32, quad.z as f32 + quad.width as f32], // 3: coin haut-droite
-                    ],
-                    vec![[1.0, 0.0, 0.0]; 4],
-                )
-            },
-        };
-
-        // Calculer les UVs basés sur les coordonnées mondiales des vertices
-        // La texture se répète tous les 10 blocs
-        fn compute_uvs(
-            u_start: f32,
-            u_size: f32,
-            v_start: f32,
-            v_size: f32,
-            repeat_frequency: f32,
-            base_uv: [f32; 2],
-            size_uv: [f32; 2],
-        ) -> [[f32; 2]; 4] {
-            let u1 = (u_start / repeat_frequency) / repeat_frequency;
-            let u2 = ((u_start + u_size) / repeat_frequency) / repeat_frequency;
-            let v1 = (v_start / repeat_frequency) / repeat_frequency;
-            let v2 = ((v_start + v_size) / repeat_frequency) / repeat_frequency;
-
-            let uv_tl = [base_uv[0] + u1 * size_uv[0], base_uv[1] + v2 * size_uv[1]];
-            let uv_tr = [base_uv[0] + u2 * size_uv[0], base_uv[1] + v2 * size_uv[1]];
-            let uv_br = [base_uv[0] + u2 * size_uv[0], base_uv[1] + v1 * size_uv[1]];
-            let uv_bl = [base_uv[0] + u1 * size_uv[0], base_uv[1] + v1 * size_uv[1]];
-
-            [uv_tl, uv_tr, uv_br, uv_bl]
+        let vertex_offset = vertices.len() as u32;
+        let vertex_ao = ao_corner_for_vertices(quad.direction).map(|corner| quad.ao[corner]);
+
+        // Sommets à la surface : toute la face du dessus, et le bord haut des faces latérales
+        let surface_y = (quad.y + quad.height) as u32;
+        let biome_tint = quad.type_blocks.definition().biome_tint_for(quad.direction);
+        for (corner, ao) in quad_corners(quad).into_iter().zip(vertex_ao) {
+            let tint = pack_tint(tints.vertex_tint(quad.type_blocks, biome_tint, corner[0] as f32, corner[2] as f32));
+            let surface = quad.surface && match quad.direction {
+                Direction::Up => true,
+                Direction::Down => false,
+                _ => corner[1] == surface_y,
+            };
+            vertices.push(pack_vertex(corner, quad.direction, ao, quad.sky_light, quad.block_light, surface, quad.texture, tint));
         }
+        indices.extend(quad_indices(vertex_ao).map(|i| vertex_offset + i));
+    }
+
+    // Une section compte au plus 16³ * 6 faces, soit moins de 65536 sommets dans presque
+    // tous les cas : des indices sur 16 bits suffisent
+    let indices = if vertices.len() <= u16::MAX as usize + 1 {
+        Indices::U16(indices.into_iter().map(|i| i as u16).collect())
+    } else {
+        Indices::U32(indices)
+    };
+
+    mesh.insert_attribute(ATTRIBUTE_PACKED_VOXEL, vertices);
+    mesh.insert_indices(indices);
+
+    mesh
+}
+
+/// Mesh des faces de modèles. Leurs positions ne tiennent pas dans le format compressé :
+/// ce mesh garde des positions, normales et UVs flottantes, et n'utilise de
+/// `ATTRIBUTE_PACKED_VOXEL` que la lumière, la couche et la teinte (voir le shader, `VOXEL_MODEL`).
+pub fn model_faces_to_mesh(faces: &[ModelFace], tints: &BiomeTints) -> Mesh {
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default());
 
-        let uvs_calculated = match quad.direction {
-            Direction::Up | Direction::Down => compute_uvs(
-                quad.x as f32,
-                quad.width as f32,
-                quad.z as f32,
-                quad.height as f32,
-                repeat_frequency,
-                *base_uv,
-                *size_uv,
-            ),
-            Direction::North | Direction::South => compute_uvs(
-                quad.x as f32,
-                quad.width as f32,
-                quad.y as f32,
-                quad.height as f32,
-                repeat_frequency,
-                *base_uv,
-                *size_uv,
-            ),
-            Direction::East | Direction::West => compute_uvs(
-                quad.z as f32,
-                quad.width as f32,
-                quad.y as f32,
-                quad.height as f32,
-                repeat_frequency,
-                *base_uv,
-                *size_uv,
-            ),
-        };
-
-        positions.extend_from_slice(&quad_positions);
-        normals.extend_from_slice(&quad_normals);
-        uvs.extend_from_slice(&uvs_calculated);
-
-        // Indices pour former deux triangles (sens anti-horaire vu de l'extérieur)
-        indices.extend_from_slice(&[
-            vertex_offset, vertex_offset + 1, vertex_offset + 2,
-            vertex_offset + 2, vertex_offset + 3, vertex_offset,
-        ]);
-
-        vertex_offset += 4;
+    let mut positions = Vec::with_capacity(faces.len() * 4);
+    let mut normals = Vec::with_capacity(faces.len() * 4);
+    let mut uvs = Vec::with_capacity(faces.len() * 4);
+    let mut packed = Vec::with_capacity(faces.len() * 4);
+    let mut indices = Vec::with_capacity(faces.len() * 6);
+
+    for face in faces {
+        let vertex_offset = positions.len() as u32;
+        let biome_tint = face.block.definition().biome_tint;
+
+        positions.extend(face.positions.map(|p| p.to_array()));
+        normals.extend([face.normal.to_array(); 4]);
+        uvs.extend(face.uvs.map(|uv| uv.to_array()));
+        packed.extend(face.positions.map(|p| {
+            let tint = pack_tint(tints.vertex_tint(face.block, biome_tint, p.x, p.z));
+            pack_vertex([0; 3], Direction::Up, 3, face.sky_light, face.block_light, false, face.texture, tint)
+        }));
+        indices.extend([0, 1, 2, 2, 3, 0].map(|i| vertex_offset + i));
     }
 
     mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
     mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
     mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.insert_attribute(ATTRIBUTE_PACKED_VOXEL, packed);
     mesh.insert_indices(Indices::U32(indices));
 
     mesh
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum Direction {
-    Up,
-    Down,
-    North,
-    South,
-    East,
-    West,
+/// Découpe un quad fusionné en quads d'un bloc, pour les formats qui ne savent pas
+/// répéter une texture (UVs dans un atlas)
+pub fn split_into_cells(quad: &Quad) -> Vec<Quad> {
+    let (u_axis, v_axis) = get_uv_axes(quad.direction);
+    let mut cells = Vec::with_capacity(quad.width * quad.height);
+    for v in 0..quad.height {
+        for u in 0..quad.width {
+            cells.push(Quad {
+                x: quad.x + u * u_axis.0 as usize + v * v_axis.0 as usize,
+                y: quad.y + u * u_axis.1 as usize + v * v_axis.1 as usize,
+                z: quad.z + u * u_axis.2 as usize + v * v_axis.2 as usize,
+                width: 1,
+                height: 1,
+                direction: quad.direction,
+                type_blocks: quad.type_blocks,
+                texture: quad.texture,
+                ao: quad.ao,
+                sky_light: quad.sky_light,
+                block_light: quad.block_light,
+                surface: quad.surface,
+            });
+        }
+    }
+    cells
 }
 
-pub fn generate_quads_for_section(section: &ChunkSection) -> (Vec<Quad>, Vec<Quad>) {
-    let mut opaque_quads = Vec::new();
-    let mut water_quads = Vec::new();
+/// AABB locale englobant tous les quads (None s'il n'y en a aucun)
+fn quads_aabb(quads: &[Quad]) -> Option<Aabb> {
+    let mut corners = quads.iter().flat_map(quad_corners).map(|c| Vec3::new(c[0] as f32, c[1] as f32, c[2] as f32));
+    let first = corners.next()?;
+    let (min, max) = corners.fold((first, first), |(min, max), c| (min.min(c), max.max(c)));
+    Some(Aabb::from_min_max(min, max))
+}
+
+fn model_faces_aabb(faces: &[ModelFace]) -> Option<Aabb> {
+    let mut corners = faces.iter().flat_map(|face| face.positions);
+    let first = corners.next()?;
+    let (min, max) = corners.fold((first, first), |(min, max), c| (min.min(c), max.max(c)));
+    Some(Aabb::from_min_max(min, max))
+}
+
+/// Quads d'une section, rangés par couche de rendu (indice `RenderLayer::index`)
+pub type LayerQuads = [Vec<Quad>; 3];
+
+/// Quads de la section, en cases de `neighborhood.scale()` blocs : en LOD, les coordonnées
+/// et tailles des quads sont exprimées en cases, et le mesh est agrandi par sa transformation.
+pub fn generate_quads_for_section(neighborhood: &ChunkNeighborhood, section: &ChunkSection) -> LayerQuads {
+    let mut quads = LayerQuads::default();
 
     // Générer les quads pour chaque direction
-    for direction in [Direction::Up, Direction::Down, Direction::North, Direction::South, Direction::East, Direction::West] {
-        let (mut opaque, mut water) = generate_quads_for_direction(section, direction);
-        opaque_quads.append(&mut opaque);
-        water_quads.append(&mut water);
+    for direction in Direction::ALL {
+        let layers = generate_quads_for_direction(neighborhood, section, direction);
+        for (all, mut layer) in quads.iter_mut().zip(layers) {
+            all.append(&mut layer);
+        }
     }
 
-    (opaque_quads, water_quads)
+    quads
 }
 
-fn generate_quads_for_direction(section: &ChunkSection, direction: Direction) -> (Vec<Quad>, Vec<Quad>) {
-    let mut opaque_quads = Vec::new();
-    let mut water_quads = Vec::new();
+fn generate_quads_for_direction(neighborhood: &ChunkNeighborhood, section: &ChunkSection, direction: Direction) -> LayerQuads {
+    let mut quads = LayerQuads::default();
 
     // Dimensions selon la direction
-    let (u_max, v_max, w_max) = get_dimensions_for_direction(direction);
+    let (u_max, v_max, w_max) = get_dimensions_for_direction(direction, neighborhood.scale());
 
     // Masque pour marquer les faces déjà traitées
     let mut mask = vec![vec![None; v_max]; u_max];
@@ -241,41 +285,75 @@ fn generate_quads_for_direction(section: &ChunkSection, direction: Direction) ->
         }
 
         // Remplir le masque avec les faces à rendre
-        fill_mask(&mut mask, section, direction, w);
+        fill_mask(&mut mask, neighborhood, section, direction, w);
 
         // Générer les quads à partir du masque
-        let (mut opaque, mut water) = generate_quads_from_mask(&mask, direction, w);
-        opaque_quads.append(&mut opaque);
-        water_quads.append(&mut water);
+        let layers = generate_quads_from_mask(&mask, direction, w);
+        for (all, mut layer) in quads.iter_mut().zip(layers) {
+            all.append(&mut layer);
+        }
     }
 
-    (opaque_quads, water_quads)
+    quads
 }
 
-fn get_dimensions_for_direction(direction: Direction) -> (usize, usize, usize) {
+/// Face à rendre dans le masque. Deux cellules ne sont fusionnées que si elles sont égales,
+/// donc si elles partagent la même texture, la même lumière et la même occlusion ambiante à chaque coin.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct MaskFace {
+    block: BlockType,
+    texture: u16,
+    ao: [u8; 4],
+    sky_light: u8,
+    block_light: u8,
+    surface: bool,
+}
+
+/// Nombre de cases le long des axes u, v et w, pour des cases de `scale` blocs
+fn get_dimensions_for_direction(direction: Direction, scale: usize) -> (usize, usize, usize) {
+    let cells = 16 / scale;
     match direction {
-        Direction::Up | Direction::Down => (16, 16, 16), // x, z, y
-        Direction::North | Direction::South => (16, 16, 16), // x, y, z
-        Direction::East | Direction::West => (16, 16, 16), // z, y, x
+        Direction::Up | Direction::Down => (cells, cells, cells), // x, z, y
+        Direction::North | Direction::South => (cells, cells, cells), // x, y, z
+        Direction::East | Direction::West => (cells, cells, cells), // z, y, x
     }
 }
 
-fn fill_mask(mask: &mut Vec<Vec<Option<BlockType>>>, section: &ChunkSection, direction: Direction, w: usize) {
-    let (u_max, v_max, _) = get_dimensions_for_direction(direction);
+fn fill_mask(mask: &mut Vec<Vec<Option<MaskFace>>>, neighborhood: &ChunkNeighborhood, section: &ChunkSection, direction: Direction, w: usize) {
+    let scale = neighborhood.scale();
+    let (u_max, v_max, _) = get_dimensions_for_direction(direction, scale);
 
     for u in 0..u_max {
         for v in 0..v_max {
-            let (x, y, z) = convert_uvw_to_xyz(u, v, w, direction);
-            let (nx, ny, nz) = get_neighbor_coords(x, y, z, direction);
+            // Premier bloc de la case ; en LOD, toute la case porte le même bloc
+            let (x, y, z) = convert_uvw_to_xyz(u * scale, v * scale, w * scale, direction);
+            let (nx, ny, nz) = get_neighbor_coords(x, y, z, direction, scale);
 
             let current_block = section.get_block(x, y, z);
-            let neighbor_block = get_neighbor_block(section, nx, ny, nz);
+            let neighbor_block = get_neighbor_block(neighborhood, section, nx, ny, nz);
 
             // Une face doit être rendue si :
             // 1. Le bloc actuel n'est pas de l'air
             // 2. Le voisin est de l'air ou transparent
-            if current_block != BlockType::Air && should_render_face(current_block, neighbor_block) {
-                mask[u][v] = Some(current_block);
+            // Les blocs à modèle sont maillés à part, par generate_model_faces
+            if current_block != BlockType::AIR
+                && current_block.definition().model.is_none()
+                && should_render_face(current_block, neighbor_block) {
+                if let Some(texture) = BlockRegistry::global().face_texture(current_block, direction) {
+                    // Pas d'occlusion ambiante en LOD : elle se calcule à l'échelle du bloc
+                    let ao = if scale == 1 { compute_face_ao(neighborhood, section, (nx, ny, nz), direction) } else { [3; 4] };
+                    let world_y = section.y as i32 * 16 + ny;
+                    let surface = current_block.definition().fluid
+                        && get_neighbor_block(neighborhood, section, x as i32, (y + scale) as i32, z as i32) != current_block;
+                    mask[u][v] = Some(MaskFace {
+                        block: current_block,
+                        texture,
+                        ao,
+                        sky_light: neighborhood.get_sky_light(nx, world_y, nz),
+                        block_light: neighborhood.get_block_light(nx, world_y, nz),
+                        surface,
+                    });
+                }
             }
         }
     }
@@ -289,7 +367,8 @@ fn convert_uvw_to_xyz(u: usize, v: usize, w: usize, direction: Direction) -> (us
     }
 }
 
-fn get_neighbor_coords(x: usize, y: usize, z: usize, direction: Direction) -> (i32, i32, i32) {
+/// Coordonnées de la case voisine dans `direction`, à `step` blocs
+fn get_neighbor_coords(x: usize, y: usize, z: usize, direction: Direction, step: usize) -> (i32, i32, i32) {
     let (dx, dy, dz) = match direction {
         Direction::Up => (0, 1, 0),
         Direction::Down => (0, -1, 0),
@@ -299,67 +378,153 @@ fn get_neighbor_coords(x: usize, y: usize, z: usize, direction: Direction) -> (i
         Direction::West => (-1, 0, 0),
     };
 
-    (x as i32 + dx, y as i32 + dy, z as i32 + dz)
+    let step = step as i32;
+    (x as i32 + dx * step, y as i32 + dy * step, z as i32 + dz * step)
 }
 
-fn get_neighbor_block(section: &ChunkSection, x: i32, y: i32, z: i32) -> BlockType {
+fn get_neighbor_block(neighborhood: &ChunkNeighborhood, section: &ChunkSection, x: i32, y: i32, z: i32) -> BlockType {
     if x < 0 || y < 0 || z < 0 || x >= 16 || y >= 16 || z >= 16 {
-        BlockType::Air // Considérer l'extérieur comme de l'air
+        // Hors de la section : lire la section voisine, éventuellement dans un chunk voisin.
+        // Un chunk voisin pas encore chargé compte comme de l'air ; la bordure sera
+        // remaillée quand il arrivera.
+        neighborhood.get_block(x, section.y as i32 * 16 + y, z)
     } else {
         section.get_block(x as usize, y as usize, z as usize)
     }
 }
 
+/// Axes u et v du masque, en coordonnées (x, y, z), pour une direction
+fn get_uv_axes(direction: Direction) -> ((i32, i32, i32), (i32, i32, i32)) {
+    match direction {
+        Direction::Up | Direction::Down => ((1, 0, 0), (0, 0, 1)),
+        Direction::North | Direction::South => ((1, 0, 0), (0, 1, 0)),
+        Direction::East | Direction::West => ((0, 0, 1), (0, 1, 0)),
+    }
+}
+
+/// Occlusion ambiante des 4 coins d'une face, calculée dans le plan de la case voisine
+/// (devant la face) à partir des deux côtés et du coin adjacents à chaque sommet.
+fn compute_face_ao(neighborhood: &ChunkNeighborhood, section: &ChunkSection, front: (i32, i32, i32), direction: Direction) -> [u8; 4] {
+    let (u_axis, v_axis) = get_uv_axes(direction);
+    let occludes = |du: i32, dv: i32| {
+        let x = front.0 + du * u_axis.0 + dv * v_axis.0;
+        let y = front.1 + du * u_axis.1 + dv * v_axis.1;
+        let z = front.2 + du * u_axis.2 + dv * v_axis.2;
+        get_neighbor_block(neighborhood, section, x, y, z).definition().is_full_cube()
+    };
+
+    [(-1, -1), (1, -1), (1, 1), (-1, 1)].map(|(su, sv)| {
+        let side1 = occludes(su, 0);
+        let side2 = occludes(0, sv);
+        if side1 && side2 {
+            0
+        } else {
+            3 - side1 as u8 - side2 as u8 - occludes(su, sv) as u8
+        }
+    })
+}
+
 fn should_render_face(current: BlockType, neighbor: BlockType) -> bool {
-    // Rendre la face si le voisin est transparent ou différent
-    match (current, neighbor) {
-        (BlockType::Air, _) => false,
-        (_, BlockType::Air) => true,
-        (BlockType::Water, BlockType::Water) => false,
-        (BlockType::Water, _) => true,
-        (_, BlockType::Water) => true,
-        (a, b) if a == b => false,
-        _ => true,
+    // Rendre la face si le voisin laisse voir à travers lui (air, eau...).
+    // Entre deux blocs identiques, seules les couches découpées (feuilles) gardent leurs faces :
+    // verre contre verre ou eau contre eau ne dessine rien.
+    if current == BlockType::AIR {
+        return false;
+    }
+    if current == neighbor {
+        return current.definition().render_layer == RenderLayer::Cutout;
+    }
+    // Un fluide contre un cube solide ne se voit pas, même si ce cube est transparent (verre, glace).
+    // Contre une dalle ou un escalier, la face reste : le modèle ne couvre pas toute la case.
+    let neighbor_definition = neighbor.definition();
+    if current.definition().fluid && neighbor_definition.solid && neighbor_definition.model.is_none() {
+        return false;
     }
+    !neighbor_definition.is_full_cube()
 }
 
-fn generate_quads_from_mask(mask: &Vec<Vec<Option<BlockType>>>, direction: Direction, w: usize) -> (Vec<Quad>, Vec<Quad>) {
-    let mut opaque_quads = Vec::new();
-    let mut water_quads = Vec::new();
+/// Faces des blocs à modèle de la section, rangées par couche de rendu, et les boîtes de
+/// collision de ceux qui sont solides. Une face avec `cullface` disparaît contre un cube plein.
+pub fn generate_model_faces(neighborhood: &ChunkNeighborhood, section: &ChunkSection) -> [Vec<ModelFace>; 3] {
+    let registry = BlockRegistry::global();
+    let mut faces: [Vec<ModelFace>; 3] = Default::default();
+
+    let has_models = section.palette_blocks().iter().any(|&block| registry.model(block).is_some());
+    if !has_models {
+        return faces;
+    }
+
+    for y in 0..16 {
+        for z in 0..16 {
+            for x in 0..16 {
+                let block = section.get_block(x, y, z);
+                let Some(model) = registry.model(block) else {
+                    continue;
+                };
+                let definition = block.definition();
+                let offset = Vec3::new(x as f32, y as f32, z as f32);
+
+                // Lumière de la case du bloc lui-même, qui n'est pas un cube plein
+                let world_y = section.y as i32 * 16 + y as i32;
+                let sky_light = neighborhood.get_sky_light(x as i32, world_y, z as i32);
+                let block_light = neighborhood.get_block_light(x as i32, world_y, z as i32);
+
+                for quad in &model.quads {
+                    if let Some(direction) = quad.cullface {
+                        let (nx, ny, nz) = get_neighbor_coords(x, y, z, direction, 1);
+                        if get_neighbor_block(neighborhood, section, nx, ny, nz).definition().is_full_cube() {
+                            continue;
+                        }
+                    }
+                    faces[definition.render_layer.index()].push(ModelFace {
+                        positions: quad.positions.map(|p| p + offset),
+                        normal: quad.normal,
+                        uvs: quad.uvs,
+                        texture: quad.texture,
+                        block,
+                        sky_light,
+                        block_light,
+                    });
+                }
+            }
+        }
+    }
+
+    faces
+}
+
+fn generate_quads_from_mask(mask: &Vec<Vec<Option<MaskFace>>>, direction: Direction, w: usize) -> LayerQuads {
+    let mut quads = LayerQuads::default();
     let mut visited = vec![vec![false; mask[0].len()]; mask.len()];
 
     for u in 0..mask.len() {
         for v in 0..mask[0].len() {
-            if let Some(block_type) = mask[u][v] {
+            if let Some(face) = mask[u][v] {
                 if !visited[u][v] {
-                    let quad = create_quad_from_position(&mask, &mut visited, u, v, w, direction, block_type);
-
-                    if block_type == BlockType::Water {
-                        water_quads.push(quad);
-                    } else if block_type != BlockType::Air {
-                        opaque_quads.push(quad);
-                    }
+                    let layer = face.block.definition().render_layer;
+                    let quad = create_quad_from_position(&mask, &mut visited, u, v, w, direction, face);
+                    quads[layer.index()].push(quad);
                 }
             }
         }
     }
 
-    (opaque_quads, water_quads)
+    quads
 }
 
 fn create_quad_from_position(
-    mask: &Vec<Vec<Option<BlockType>>>,
+    mask: &Vec<Vec<Option<MaskFace>>>,
     visited: &mut Vec<Vec<bool>>,
     start_u: usize,
     start_v: usize,
     w: usize,
     direction: Direction,
-    block_type: BlockType,
+    face: MaskFace,
 ) -> Quad {
     // Déterminer la largeur maximale du quad (direction u)
     let mut width = 1;
     while start_u + width < mask.len() {
-        if mask[start_u + width][start_v] == Some(block_type) && !visited[start_u + width][start_v] {
+        if mask[start_u + width][start_v] == Some(face) && !visited[start_u + width][start_v] {
             width += 1;
         } else {
             break;
@@ -371,7 +536,7 @@ fn create_quad_from_position(
     'height_loop: while start_v + height < mask[0].len() {
         // Vérifier que toute la ligne est compatible
         for u in start_u..start_u + width {
-            if mask[u][start_v + height] != Some(block_type) || visited[u][start_v + height] {
+            if mask[u][start_v + height] != Some(face) || visited[u][start_v + height] {
                 break 'height_loop;
             }
         }
@@ -402,29 +567,264 @@ fn create_quad_from_position(
         width: final_width,
         height: final_height,
         direction,
-        type_blocks: block_type,
+        type_blocks: face.block,
+        texture: face.texture,
+        ao: face.ao,
+        sky_light: face.sky_light,
+        block_light: face.block_light,
+        surface: face.surface,
     }
 }
 
-pub async fn generate_mesh_from_chunk(chunk: &Chunk, texture_atlas: &TextureAtlasMaterial) -> Vec<(Mesh, Mesh, Transform)> {
-    let mut meshes = Vec::new();
-    let chunk_x = chunk.x;
-    let chunk_z = chunk.z;
+/// Trie les faces du plus éloigné au plus proche de `viewer` (en coordonnées locales à la section),
+/// pour que le mélange des couches translucides se fasse dans le bon ordre
+fn sort_back_to_front<T>(faces: &mut [T], viewer: Vec3, center: impl Fn(&T) -> Vec3) {
+    faces.sort_by(|a, b| center(b).distance_squared(viewer).total_cmp(&center(a).distance_squared(viewer)));
+}
+
+fn quad_center(quad: &Quad) -> Vec3 {
+    quad_corners(quad).iter()
+        .map(|c| Vec3::new(c[0] as f32, c[1] as f32, c[2] as f32))
+        .sum::<Vec3>() / 4.0
+}
+
+/// Maille une seule section du chunk central. `viewer` est la position de la caméra
+/// au moment du maillage, utilisée pour trier les faces translucides.
+pub fn generate_section_mesh(neighborhood: &ChunkNeighborhood, section: &ChunkSection, tints: &BiomeTints, viewer: Vec3) -> SectionMesh {
+    let chunk = neighborhood.center();
+    let scale = neighborhood.scale();
+    let mut quads = generate_quads_for_section(neighborhood, section);
+    // Les modèles (plantes, dalles...) sont des détails trop fins pour les niveaux de détail
+    let mut model_faces = if scale == 1 { generate_model_faces(neighborhood, section) } else { Default::default() };
+
+    // En LOD, les quads sont en cases de `scale` blocs : le mesh est agrandi d'autant
+    let transform = Transform::from_xyz(
+        (chunk.x * 16) as f32,
+        (section.y as i32 * 16) as f32,
+        (chunk.z * 16) as f32,
+    ).with_scale(Vec3::splat(scale as f32));
+
+    let local_viewer = (viewer - transform.translation) / scale as f32;
+    let translucent = RenderLayer::Translucent.index();
+    sort_back_to_front(&mut quads[translucent], local_viewer, quad_center);
+    sort_back_to_front(&mut model_faces[translucent], local_viewer, |face| face.positions.iter().sum::<Vec3>() / 4.0);
+
+    let mut layers = Vec::new();
+    for layer in RenderLayer::ALL {
+        let layer_quads = &quads[layer.index()];
+        if let Some(aabb) = quads_aabb(layer_quads) {
+            layers.push(LayerMesh { layer, mesh: quads_to_mesh(layer_quads, tints), aabb });
+        }
+        let layer_faces = &model_faces[layer.index()];
+        if let Some(aabb) = model_faces_aabb(layer_faces) {
+            layers.push(LayerMesh { layer, mesh: model_faces_to_mesh(layer_faces, tints), aabb });
+        }
+    }
+
+    SectionMesh {
+        section_y: section.y,
+        layers,
+        transform,
+    }
+}
+
+/// Maille toutes les sections du chunk central
+pub async fn generate_mesh_from_chunk(neighborhood: &ChunkNeighborhood, viewer: Vec3) -> Vec<SectionMesh> {
+    // Les couleurs des biomes ne dépendent que des colonnes : calculées une fois pour tout le chunk
+    let tints = BiomeTints::for_chunk(neighborhood);
+    neighborhood.center().sections.iter()
+        .map(|section| generate_section_mesh(neighborhood, section, &tints, viewer))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use crate::constants::SECTION_HEIGHT;
+    use crate::world::load_save_chunk::WorldData;
+    use crate::world::world_config::WorldConfig;
+
+    fn full_section(y: i8, block: BlockType) -> ChunkSection {
+        let mut section = ChunkSection::new(y);
+        section.set_palette(vec![block.to_string()]);
+        section
+    }
+
+    /// Section d'air avec quelques blocs posés (coordonnées locales)
+    fn section_with(y: i8, blocks: &[((usize, usize, usize), BlockType)]) -> ChunkSection {
+        let mut section = full_section(y, BlockType::AIR);
+        for &((x, y, z), block) in blocks {
+            section.set_block(x, y, z, block);
+        }
+        section
+    }
+
+    /// Voisinage du chunk (0, 0), seul chunk chargé
+    fn neighborhood_of(sections: Vec<ChunkSection>) -> ChunkNeighborhood {
+        let mut chunk = Chunk::new(0, 0);
+        chunk.sections = sections;
+        let mut world = WorldData::new(Arc::new(WorldConfig::default()));
+        world.chunks_loaded.insert((0, 0), Arc::new(chunk));
+        ChunkNeighborhood::new(&world, 0, 0, None).unwrap()
+    }
+
+    fn block(name: &str) -> BlockType {
+        BlockRegistry::global().by_name(name).unwrap()
+    }
+
+    #[test]
+    fn no_faces_between_two_solid_sections() {
+        let lower_y = WorldConfig::default().min_section();
+        let upper_y = lower_y + 1;
 
-    for section in &chunk.sections {
-        let (opaque_quads, water_quads) = generate_quads_for_section(section);
+        let neighborhood = neighborhood_of(vec![full_section(lower_y, BlockType::ROCK), full_section(upper_y, BlockType::ROCK)]);
 
-        let opaque_mesh = quads_to_mesh(&opaque_quads, &texture_atlas.uv_map);
-        let water_mesh = quads_to_mesh(&water_quads, &texture_atlas.uv_map);
+        let center = neighborhood.center();
+        let lower = generate_quads_for_section(&neighborhood, center.section(lower_y).unwrap());
+        let upper = generate_quads_for_section(&neighborhood, center.section(upper_y).unwrap());
 
-        let transform = Transform::from_xyz(
-            (chunk_x * 16) as f32,
-            (section.y as i32 * 16) as f32,
-            (chunk_z * 16) as f32,
-        );
+        let top_of_lower = lower.iter().flatten()
+            .filter(|quad| quad.direction == Direction::Up && quad.y == SECTION_HEIGHT - 1)
+            .count();
+        let bottom_of_upper = upper.iter().flatten()
+            .filter(|quad| quad.direction == Direction::Down && quad.y == 0)
+            .count();
+        assert_eq!(top_of_lower, 0, "faces du haut de la section inférieure à la jonction");
+        assert_eq!(bottom_of_upper, 0, "faces du bas de la section supérieure à la jonction");
+
+        // Les faces extérieures de la colonne restent bien générées
+        assert!(upper.iter().flatten().any(|quad| quad.direction == Direction::Up));
+    }
 
-        meshes.push((opaque_mesh, water_mesh, transform));
+    /// Quad du dessus qui couvre le bloc (x, y, z) ; sur le dessus, u = x et v = z
+    fn top_quad_at(quads: &LayerQuads, (x, y, z): (usize, usize, usize)) -> &Quad {
+        quads.iter().flatten()
+            .find(|quad| quad.direction == Direction::Up && quad.y == y
+                && (quad.x..quad.x + quad.width).contains(&x)
+                && (quad.z..quad.z + quad.height).contains(&z))
+            .expect("aucun quad du dessus sur ce bloc")
     }
 
-    meshes
-}
\ No newline at end of file
+    #[test]
+    fn ambient_occlusion_darkens_corners_against_blocks() {
+        let y = WorldConfig::default().min_section();
+        let mut blocks: Vec<_> = (0..16).flat_map(|x| (0..16).map(move |z| ((x, 0, z), BlockType::ROCK))).collect();
+        // Deux côtés d'un coin, sans le coin lui-même, puis un coin seul
+        blocks.extend([((6, 1, 5), BlockType::ROCK), ((5, 1, 6), BlockType::ROCK), ((11, 1, 11), BlockType::ROCK)]);
+        let neighborhood = neighborhood_of(vec![section_with(y, &blocks)]);
+        let quads = generate_quads_for_section(&neighborhood, neighborhood.center().section(y).unwrap());
+
+        // Coins (u0,v0), (u1,v0), (u1,v1), (u0,v1)
+        assert_eq!(top_quad_at(&quads, (5, 0, 5)).ao, [3, 2, 0, 2]);
+        assert_eq!(top_quad_at(&quads, (10, 0, 10)).ao, [3, 3, 2, 3]);
+        assert_eq!(top_quad_at(&quads, (1, 0, 1)).ao, [3; 4]);
+
+        // Le quad est coupé selon la diagonale la plus claire
+        assert_eq!(quad_indices([0, 3, 3, 3]), [1, 2, 3, 3, 0, 1]);
+        assert_eq!(quad_indices([3, 0, 3, 3]), [0, 1, 2, 2, 3, 0]);
+    }
+
+    #[test]
+    fn cutout_keeps_faces_between_identical_blocks_translucent_does_not() {
+        let (leaves, glass) = (block("minecraft:oak_leaves"), block("minecraft:glass"));
+        let y = WorldConfig::default().min_section();
+        let neighborhood = neighborhood_of(vec![section_with(y, &[
+            ((1, 1, 1), leaves), ((2, 1, 1), leaves),
+            ((1, 1, 5), glass), ((2, 1, 5), glass),
+        ])]);
+        let quads = generate_quads_for_section(&neighborhood, neighborhood.center().section(y).unwrap());
+        let cutout = &quads[RenderLayer::Cutout.index()];
+        let translucent = &quads[RenderLayer::Translucent.index()];
+
+        assert!(quads[RenderLayer::Opaque.index()].is_empty());
+        // Feuilles contre feuilles : les deux faces communes restent
+        assert!(cutout.iter().any(|quad| quad.direction == Direction::East && quad.x == 1));
+        assert!(cutout.iter().any(|quad| quad.direction == Direction::West && quad.x == 2));
+        // Verre contre verre : aucune face commune, et le dessus est un seul quad
+        assert!(!translucent.iter().any(|quad| quad.direction == Direction::East && quad.x == 1));
+        assert!(!translucent.iter().any(|quad| quad.direction == Direction::West && quad.x == 2));
+        assert!(translucent.iter().any(|quad| quad.direction == Direction::Up && quad.width == 2));
+    }
+
+    #[test]
+    fn water_faces_hidden_only_by_whole_cubes() {
+        let water = BlockType::WATER;
+        assert!(!should_render_face(water, BlockType::ROCK));
+        assert!(!should_render_face(water, block("minecraft:glass")));
+        assert!(!should_render_face(water, water));
+        assert!(should_render_face(water, BlockType::AIR));
+        assert!(should_render_face(water, block("minecraft:rock_slab")));
+        assert!(should_render_face(water, block("minecraft:rock_stairs")));
+        assert!(should_render_face(water, block("minecraft:oak_fence")));
+    }
+
+    /// Faces de modèle de la section, toutes couches confondues
+    fn model_faces_of(blocks: &[((usize, usize, usize), BlockType)]) -> Vec<ModelFace> {
+        let y = WorldConfig::default().min_section();
+        let neighborhood = neighborhood_of(vec![section_with(y, blocks)]);
+        generate_model_faces(&neighborhood, neighborhood.center().section(y).unwrap()).into_iter().flatten().collect()
+    }
+
+    /// Le bloc (4, 4, 4) entouré de roche sur ses 6 faces
+    fn enclosed(block: BlockType) -> Vec<((usize, usize, usize), BlockType)> {
+        let rock = BlockType::ROCK;
+        vec![((4, 4, 4), block), ((3, 4, 4), rock), ((5, 4, 4), rock), ((4, 3, 4), rock), ((4, 5, 4), rock), ((4, 4, 3), rock), ((4, 4, 5), rock)]
+    }
+
+    #[test]
+    fn cross_model_faces_are_never_culled() {
+        let tall_grass = block("minecraft:tall_grass");
+        let y = WorldConfig::default().min_section();
+        let neighborhood = neighborhood_of(vec![section_with(y, &[((4, 4, 4), tall_grass)])]);
+        let faces = generate_model_faces(&neighborhood, neighborhood.center().section(y).unwrap());
+
+        assert_eq!(faces[RenderLayer::Cutout.index()].len(), 4);
+        assert_eq!(model_faces_of(&enclosed(tall_grass)).len(), 4);
+        // Les deux plans se croisent en diagonale du bloc
+        assert!(faces[RenderLayer::Cutout.index()].iter().all(|face| face.normal.y.abs() < 1e-5 && face.normal.x.abs() > 0.1));
+    }
+
+    #[test]
+    fn slab_model_culls_only_its_full_faces() {
+        let slab = block("minecraft:rock_slab");
+        assert_eq!(model_faces_of(&[((4, 4, 4), slab)]).len(), 6);
+
+        // Le dessus, à mi-hauteur, n'a pas de cullface : il reste sous un bloc plein
+        let faces = model_faces_of(&enclosed(slab));
+        assert_eq!(faces.len(), 1);
+        assert!(faces[0].normal.abs_diff_eq(Vec3::Y, 1e-5));
+        assert!(faces[0].positions.iter().all(|p| (p.y - 4.5).abs() < 1e-5));
+    }
+
+    #[test]
+    fn stairs_model_keeps_its_inner_faces() {
+        let stairs = block("minecraft:rock_stairs");
+        assert_eq!(model_faces_of(&[((4, 4, 4), stairs)]).len(), 11);
+        // Restent le dessus de la marche basse et le côté ouest de la marche haute
+        assert_eq!(model_faces_of(&enclosed(stairs)).len(), 2);
+    }
+
+    #[test]
+    fn fence_model_culls_only_top_and_bottom() {
+        let fence = block("minecraft:oak_fence");
+        assert_eq!(model_faces_of(&[((4, 4, 4), fence)]).len(), 6);
+        let faces = model_faces_of(&enclosed(fence));
+        assert_eq!(faces.len(), 4);
+        assert!(faces.iter().all(|face| face.normal.y.abs() < 1e-5));
+    }
+
+    #[test]
+    fn translucent_faces_sorted_back_to_front() {
+        let glass = block("minecraft:glass");
+        let y = WorldConfig::default().min_section();
+        let neighborhood = neighborhood_of(vec![section_with(y, &[((0, 0, 0), glass), ((6, 0, 0), glass), ((12, 0, 0), glass)])]);
+        let mut quads = generate_quads_for_section(&neighborhood, neighborhood.center().section(y).unwrap());
+        let translucent = &mut quads[RenderLayer::Translucent.index()];
+
+        let viewer = Vec3::new(-4.0, 0.5, 0.5);
+        sort_back_to_front(translucent, viewer, quad_center);
+        let distances: Vec<f32> = translucent.iter().map(|quad| quad_center(quad).distance(viewer)).collect();
+        assert!(distances.windows(2).all(|pair| pair[0] >= pair[1]));
+    }
+}
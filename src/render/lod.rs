@@ -0,0 +1,221 @@
+use std::collections::{HashMap, HashSet};
+use bevy::app::{App, Plugin, Update};
+use bevy::math::IVec2;
+use bevy::prelude::{DetectChanges, EventWriter, Res, ResMut, Resource};
+use crate::constants::{CHUNK_SIZE, SECTION_HEIGHT};
+use crate::render::chunk_loadings_mesh_logic::ChunkToUpdateEvent;
+use crate::world::block::{BlockRegistry, BlockType};
+use crate::world::chunk::{Chunk, ChunkSection};
+use crate::world::chunk_loadings_logic::PlayerChunk;
+use crate::world::chunk_neighborhood::ChunkNeighborhood;
+use crate::world::load_save_chunk::WorldData;
+
+/// Niveau de détail le plus grossier : voxels de 8x8x8
+pub const MAX_LOD: u8 = 3;
+
+/// Distances (en chunks, depuis le chunk du joueur) à partir desquelles
+/// on passe aux niveaux de détail 1 (2x), 2 (4x) et 3 (8x)
+#[derive(Resource, Clone)]
+pub struct LodSettings {
+    pub distances: [i32; MAX_LOD as usize],
+}
+
+impl Default for LodSettings {
+    fn default() -> Self {
+        Self { distances: [8, 12, 16] }
+    }
+}
+
+impl LodSettings {
+    pub fn lod_for_distance(&self, distance: f32) -> u8 {
+        self.distances.iter().filter(|&&d| distance > d as f32).count() as u8
+    }
+
+    pub fn lod_for_chunk(&self, player_chunk: IVec2, x: i32, z: i32) -> u8 {
+        let distance = IVec2::new(x, z).as_vec2().distance(player_chunk.as_vec2());
+        self.lod_for_distance(distance)
+    }
+}
+
+/// Voisins qui partagent une face avec un chunk : seuls eux peuvent cacher ses faces de bordure
+const FACE_NEIGHBORS: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+/// Niveau de détail de chacun des voisins de `FACE_NEIGHBORS`
+pub type NeighborLods = [u8; 4];
+
+pub fn neighbor_lods(x: i32, z: i32, lod_of: impl Fn(i32, i32) -> u8) -> NeighborLods {
+    FACE_NEIGHBORS.map(|(dx, dz)| lod_of(x + dx, z + dz))
+}
+
+/// Niveau de détail avec lequel chaque chunk a été (ou est en train d'être) maillé
+#[derive(Resource, Default)]
+pub struct ChunkLods {
+    pub levels: HashMap<(i32, i32), u8>,
+}
+
+pub struct ChunkLodPlugin;
+
+impl Plugin for ChunkLodPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LodSettings>();
+        app.init_resource::<ChunkLods>();
+        app.add_systems(Update, update_chunk_lods);
+    }
+}
+
+/// Quand le joueur change de chunk ou que les distances changent, remaille les chunks dont le niveau de détail doit changer
+fn update_chunk_lods(
+    player_chunk: Res<PlayerChunk>,
+    settings: Res<LodSettings>,
+    mut chunk_lods: ResMut<ChunkLods>,
+    world_data: Res<WorldData>,
+    mut chunk_to_update: EventWriter<ChunkToUpdateEvent>,
+) {
+    if !player_chunk.is_changed() && !settings.is_changed() {
+        return;
+    }
+
+    chunk_lods.levels.retain(|pos, _| world_data.chunks_loaded.contains_key(pos));
+
+    let mut to_update = HashSet::new();
+    for (&(x, z), level) in chunk_lods.levels.iter() {
+        if settings.lod_for_chunk(player_chunk.current_chunk, x, z) != *level {
+            to_update.insert((x, z));
+            // Les jupes des voisins dépendent du niveau de ce chunk
+            to_update.extend(world_data.loaded_neighbors(x, z));
+        }
+    }
+    chunk_to_update.write_batch(to_update.into_iter().map(|(x, z)| ChunkToUpdateEvent { x, z }));
+}
+
+/// Voisinage à mailler pour un niveau de détail donné. Au-delà du niveau 0, le chunk central
+/// et ses voisins de même niveau sont sous-échantillonnés, puis maillés par cases de `scale` blocs.
+/// Les voisins d'un autre niveau sont retirés : les faces de bordure de ce côté sont émises et
+/// servent de jupe qui masque les fissures entre niveaux différents.
+pub fn neighborhood_for_lod(neighborhood: ChunkNeighborhood, lod: u8, neighbor_lods: NeighborLods) -> ChunkNeighborhood {
+    if lod == 0 {
+        return neighborhood;
+    }
+    let lod = lod.min(MAX_LOD);
+    let scale = 1 << lod;
+    neighborhood.rescaled(scale, |dx, dz, chunk| {
+        let same_level = (dx, dz) == (0, 0) || FACE_NEIGHBORS.iter()
+            .position(|&offset| offset == (dx, dz))
+            .is_some_and(|i| neighbor_lods[i].min(MAX_LOD) == lod);
+        same_level.then(|| downsample_chunk(chunk, scale))
+    })
+}
+
+/// Remplace chaque cube de `scale`³ blocs par un seul type de bloc. Le mailleur ne lit ensuite
+/// qu'un bloc par cube (voir `ChunkNeighborhood::scale`).
+pub fn downsample_chunk(chunk: &Chunk, scale: usize) -> Chunk {
+    let mut result = chunk.clone();
+    for section in result.sections.iter_mut() {
+        downsample_section(section, scale);
+    }
+    result
+}
+
+fn downsample_section(section: &mut ChunkSection, scale: usize) {
+    let registry = BlockRegistry::global();
+    // Les blocs à modèle ne sont pas maillés en LOD : ils comptent comme de l'air
+    let air = section.palette_index(BlockType::AIR) as usize;
+    let empty: Vec<bool> = section.palette_blocks().iter()
+        .map(|&block| block == BlockType::AIR || registry.model(block).is_some())
+        .collect();
+    let source = section.blocks.clone();
+    let total = scale * scale * scale;
+    let mut counts = vec![0usize; empty.len()];
+
+    for cell_y in (0..SECTION_HEIGHT).step_by(scale) {
+        for cell_z in (0..CHUNK_SIZE).step_by(scale) {
+            for cell_x in (0..CHUNK_SIZE).step_by(scale) {
+                counts.iter_mut().for_each(|c| *c = 0);
+                for y in cell_y..cell_y + scale {
+                    for z in cell_z..cell_z + scale {
+                        for x in cell_x..cell_x + scale {
+                            counts[source[(y * 16 + z) * 16 + x] as usize] += 1;
+                        }
+                    }
+                }
+
+                // Plein si au moins la moitié des blocs sont des cubes,
+                // avec alors le cube le plus fréquent
+                let filled: usize = counts.iter().zip(&empty).filter(|(_, &e)| !e).map(|(c, _)| c).sum();
+                let value = if filled * 2 >= total {
+                    counts.iter().enumerate()
+                        .filter(|(i, _)| !empty[*i])
+                        .max_by_key(|(_, c)| **c)
+                        .map_or(air, |(i, _)| i)
+                } else {
+                    air
+                };
+
+                for y in cell_y..cell_y + scale {
+                    for z in cell_z..cell_z + scale {
+                        for x in cell_x..cell_x + scale {
+                            section.blocks[(y * 16 + z) * 16 + x] = value as u8;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use crate::render::generate_mesh_chunk::generate_quads_for_section;
+    use crate::world::direction::Direction;
+    use crate::world::world_config::WorldConfig;
+
+    fn rock_chunk(x: i32, z: i32, section_y: i8) -> Chunk {
+        let mut section = ChunkSection::new(section_y);
+        section.set_palette(vec![BlockType::ROCK.to_string()]);
+        let mut chunk = Chunk::new(x, z);
+        chunk.sections.push(section);
+        chunk
+    }
+
+    #[test]
+    fn border_faces_kept_only_against_a_different_level() {
+        let y = WorldConfig::default().min_section();
+        let mut world = WorldData::new(Arc::new(WorldConfig::default()));
+        world.chunks_loaded.insert((0, 0), Arc::new(rock_chunk(0, 0, y)));
+        world.chunks_loaded.insert((1, 0), Arc::new(rock_chunk(1, 0, y)));
+
+        // Aire des faces est du chunk (0, 0) maillé au niveau 1, son voisin est étant au niveau `east_lod`
+        let east_faces = |east_lod: u8| -> usize {
+            let neighborhood = ChunkNeighborhood::new(&world, 0, 0, None).unwrap();
+            let neighborhood = neighborhood_for_lod(neighborhood, 1, [east_lod, 1, 1, 1]);
+            let quads = generate_quads_for_section(&neighborhood, neighborhood.center().section(y).unwrap());
+            quads.iter().flatten()
+                .filter(|quad| quad.direction == Direction::East)
+                .map(|quad| quad.width * quad.height)
+                .sum()
+        };
+
+        // Même niveau : la bordure est cachée par le voisin
+        assert_eq!(east_faces(1), 0);
+        // Niveau différent : toute la bordure est émise en jupe, 8x8 cases de 2 blocs
+        assert_eq!(east_faces(0), 64);
+        assert_eq!(east_faces(2), 64);
+    }
+
+    #[test]
+    fn downsampling_keeps_cells_at_least_half_full() {
+        let mut section = ChunkSection::new(0);
+        section.set_palette(vec![BlockType::AIR.to_string()]);
+        // Case (0, 0, 0) de 2x2x2 : 4 blocs sur 8, case (2, 0, 0) : 3 blocs sur 8
+        for (x, y, z) in [(0, 0, 0), (1, 0, 0), (0, 1, 0), (1, 1, 1), (2, 0, 0), (3, 0, 0), (2, 1, 0)] {
+            section.set_block(x, y, z, BlockType::ROCK);
+        }
+        downsample_section(&mut section, 2);
+
+        assert_eq!(section.get_block(1, 1, 0), BlockType::ROCK);
+        assert_eq!(section.get_block(0, 0, 1), BlockType::ROCK);
+        assert_eq!(section.get_block(2, 0, 0), BlockType::AIR);
+    }
+}
@@ -0,0 +1,90 @@
+use bevy::prelude::*;
+use crate::render::voxel_material::VoxelMaterial;
+use crate::texture::TextureAtlasMaterial;
+use crate::world::load_save_chunk::WorldData;
+
+/// Couleur de l'eau vue de l'intérieur
+const UNDERWATER_COLOR: Color = Color::srgb(0.06, 0.18, 0.32);
+/// Distances (en blocs) du brouillard sous l'eau
+const UNDERWATER_FOG_START: f32 = 2.0;
+const UNDERWATER_FOG_END: f32 = 24.0;
+
+/// Vrai quand la caméra est dans un bloc de fluide
+#[derive(Resource, Default)]
+pub struct Underwater {
+    pub submerged: bool,
+}
+
+/// Voile coloré plein écran affiché sous l'eau
+#[derive(Component)]
+struct UnderwaterOverlay;
+
+pub struct UnderwaterPlugin;
+
+impl Plugin for UnderwaterPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Underwater>();
+        app.add_systems(Startup, spawn_underwater_overlay);
+        app.add_systems(Update, (detect_underwater, apply_underwater_effect).chain());
+    }
+}
+
+fn spawn_underwater_overlay(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            ..default()
+        },
+        BackgroundColor(UNDERWATER_COLOR.with_alpha(0.35)),
+        Visibility::Hidden,
+        UnderwaterOverlay,
+    ));
+}
+
+fn detect_underwater(
+    cameras: Query<&GlobalTransform, With<Camera3d>>,
+    world_data: Res<WorldData>,
+    mut underwater: ResMut<Underwater>,
+) {
+    let Some(camera) = cameras.iter().next() else {
+        return;
+    };
+    let position = camera.translation().floor();
+    let block = world_data.get_block_at(position.x as isize, position.y as isize, position.z as isize);
+
+    let submerged = block.definition().fluid;
+    if underwater.submerged != submerged {
+        underwater.submerged = submerged;
+    }
+}
+
+/// Affiche le voile et active le brouillard des matériaux des blocs quand on entre ou sort de l'eau
+fn apply_underwater_effect(
+    underwater: Res<Underwater>,
+    atlas_material: Option<Res<TextureAtlasMaterial>>,
+    mut materials: ResMut<Assets<VoxelMaterial>>,
+    mut overlays: Query<&mut Visibility, With<UnderwaterOverlay>>,
+) {
+    let Some(atlas_material) = atlas_material else {
+        return;
+    };
+    if !underwater.is_changed() && !atlas_material.is_added() {
+        return;
+    }
+
+    for mut visibility in overlays.iter_mut() {
+        *visibility = if underwater.submerged { Visibility::Visible } else { Visibility::Hidden };
+    }
+
+    let fog_color = LinearRgba::from(UNDERWATER_COLOR).to_vec4().truncate()
+        .extend(if underwater.submerged { 1.0 } else { 0.0 });
+    for handle in atlas_material.handles() {
+        if let Some(material) = materials.get_mut(handle) {
+            material.params.fog_color = fog_color;
+            material.params.fog_start = UNDERWATER_FOG_START;
+            material.params.fog_end = UNDERWATER_FOG_END;
+        }
+    }
+}
@@ -0,0 +1,309 @@
+use std::collections::HashMap;
+use std::path::Path;
+use bevy::asset::{Asset, Assets, Handle, RenderAssetUsages};
+use bevy::image::{Image, ImageAddressMode, ImageFilterMode, ImageSampler, ImageSamplerDescriptor};
+use bevy::log::{info, warn};
+use bevy::math::{Vec3, Vec4};
+use bevy::pbr::{DirectionalLight, Material, MaterialPipeline, MaterialPipelineKey};
+use bevy::pbr::light_consts::lux::AMBIENT_DAYLIGHT;
+use bevy::prelude::{AlphaMode, GlobalTransform, Query, Res, ResMut, Resource};
+use bevy::reflect::TypePath;
+use bevy::render::mesh::{Mesh, MeshVertexBufferLayoutRef};
+use bevy::render::render_resource::{
+    AsBindGroup, Extent3d, RenderPipelineDescriptor, ShaderRef, ShaderType, SpecializedMeshPipelineError,
+    TextureDimension, TextureFormat,
+};
+use crate::render::generate_mesh_chunk::ATTRIBUTE_PACKED_VOXEL;
+use crate::texture::TextureAtlasMaterial;
+use crate::world::block::BlockRegistry;
+
+pub const VOXEL_SHADER_PATH: &str = "shaders/voxel.wgsl";
+pub const VOXEL_PREPASS_SHADER_PATH: &str = "shaders/voxel_prepass.wgsl";
+/// Textures sources de l'atlas, une image par texture de bloc
+pub const BLOCK_TEXTURES_PATH: &str = "assets/textures";
+
+/// Matériau des blocs. Les sommets sont au format compressé `ATTRIBUTE_PACKED_VOXEL`,
+/// décodé par le shader : la couleur vient d'un tableau de textures (une couche par
+/// texture de bloc), éclairée par la lumière du ciel et des blocs portée par chaque sommet.
+#[derive(Asset, AsBindGroup, TypePath, Debug, Clone)]
+pub struct VoxelMaterial {
+    #[uniform(0)]
+    pub params: VoxelParams,
+    #[texture(1, dimension = "2d_array")]
+    #[sampler(2)]
+    pub array_texture: Handle<Image>,
+    pub alpha_mode: AlphaMode,
+}
+
+#[derive(ShaderType, Debug, Clone, Copy)]
+pub struct VoxelParams {
+    /// Couleur multipliée à la texture (rgb) et opacité (a)
+    pub tint: Vec4,
+    /// xyz : direction vers le soleil, w : intensité du soleil (0 la nuit, 1 en plein jour)
+    pub sun: Vec4,
+    /// Couleur du brouillard (rgb) et intensité (a, 0 = pas de brouillard)
+    pub fog_color: Vec4,
+    /// Part de la lumière du ciel qui ne dépend pas de l'orientation de la face
+    pub sky_ambient: f32,
+    /// Distances (en blocs) où le brouillard commence et devient opaque
+    pub fog_start: f32,
+    pub fog_end: f32,
+    /// Les pixels moins opaques que ce seuil sont jetés (couche découpée)
+    pub alpha_cutoff: f32,
+}
+
+impl VoxelParams {
+    pub fn with_tint(tint: Vec4) -> Self {
+        Self {
+            tint,
+            sun: Vec4::new(0.0, 1.0, 0.0, 1.0),
+            fog_color: Vec4::ZERO,
+            sky_ambient: 0.6,
+            fog_start: 0.0,
+            fog_end: 1.0,
+            alpha_cutoff: 0.0,
+        }
+    }
+}
+
+impl Material for VoxelMaterial {
+    fn vertex_shader() -> ShaderRef {
+        VOXEL_SHADER_PATH.into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        VOXEL_SHADER_PATH.into()
+    }
+
+    fn prepass_vertex_shader() -> ShaderRef {
+        VOXEL_PREPASS_SHADER_PATH.into()
+    }
+
+    fn prepass_fragment_shader() -> ShaderRef {
+        VOXEL_PREPASS_SHADER_PATH.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        self.alpha_mode
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayoutRef,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        // Les cubes n'ont que l'attribut compressé, y compris pour les ombres. Les meshes de modèles
+        // (positions flottantes) passent par la variante VOXEL_MODEL du même shader.
+        let vertex_layout = if layout.0.contains(Mesh::ATTRIBUTE_POSITION) {
+            descriptor.vertex.shader_defs.push("VOXEL_MODEL".into());
+            layout.0.get_layout(&[
+                Mesh::ATTRIBUTE_POSITION.at_shader_location(0),
+                Mesh::ATTRIBUTE_NORMAL.at_shader_location(1),
+                Mesh::ATTRIBUTE_UV_0.at_shader_location(2),
+                ATTRIBUTE_PACKED_VOXEL.at_shader_location(3),
+            ])?
+        } else {
+            layout.0.get_layout(&[ATTRIBUTE_PACKED_VOXEL.at_shader_location(0)])?
+        };
+        descriptor.vertex.buffers = vec![vertex_layout];
+        Ok(())
+    }
+}
+
+/// Recopie la direction et l'intensité du soleil dans les matériaux des blocs,
+/// qui ne passent plus par l'éclairage PBR de Bevy
+pub fn update_voxel_lighting(
+    lights: Query<(&GlobalTransform, &DirectionalLight)>,
+    atlas_material: Option<Res<TextureAtlasMaterial>>,
+    mut materials: ResMut<Assets<VoxelMaterial>>,
+) {
+    let (Some(atlas_material), Some((transform, light))) = (atlas_material, lights.iter().next()) else {
+        return;
+    };
+
+    let to_sun: Vec3 = -transform.forward().as_vec3();
+    let intensity = (light.illuminance / AMBIENT_DAYLIGHT).clamp(0.0, 1.0);
+    let sun = to_sun.extend(intensity);
+
+    for handle in atlas_material.handles() {
+        // Ne modifier l'asset que si besoin, sinon son bind group serait recréé à chaque frame
+        let needs_update = materials.get(handle).is_some_and(|m| m.params.sun.distance_squared(sun) > 1e-6);
+        if needs_update {
+            if let Some(material) = materials.get_mut(handle) {
+                material.params.sun = sun;
+            }
+        }
+    }
+}
+
+/// Atlas source et tableau de textures construit à partir de lui
+#[derive(Resource)]
+pub struct BlockTextureArray {
+    pub atlas: Handle<Image>,
+    pub array: Handle<Image>,
+    pub built: bool,
+}
+
+/// Découpe l'atlas en couches dès qu'il est chargé. Les couches sont rangées par
+/// identifiant de texture du registre, l'indice de couche d'un quad est donc `Quad::texture`.
+pub fn build_block_texture_array(
+    mut texture_array: ResMut<BlockTextureArray>,
+    atlas_material: Res<TextureAtlasMaterial>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    if texture_array.built {
+        return;
+    }
+    let Some(atlas) = images.get(&texture_array.atlas) else {
+        return;
+    };
+    let Some(atlas) = atlas.convert(TextureFormat::Rgba8UnormSrgb) else {
+        warn!("Format de l'atlas non supporté pour le tableau de textures");
+        texture_array.built = true;
+        return;
+    };
+    let Some(atlas_data) = atlas.data.as_ref() else {
+        return;
+    };
+
+    let atlas_width = atlas.width() as usize;
+    let atlas_height = atlas.height() as usize;
+    let layer_count = BlockRegistry::global().textures().count().max(1);
+
+    // Taille d'une couche : celle de la plus grande texture de l'atlas, les plus petites sont agrandies
+    let tile_size = atlas_material.uv_map.values()
+        .map(|(_, size_uv)| {
+            let width = (size_uv[0] * atlas_width as f32).round() as usize;
+            let height = (size_uv[1] * atlas_height as f32).round() as usize;
+            width.max(height)
+        })
+        .max()
+        .unwrap_or(1)
+        .max(1);
+    let mip_count = tile_size.ilog2() as usize + 1;
+
+    let texture_names: HashMap<u16, &String> = BlockRegistry::global().textures().collect();
+
+    // Ordre attendu par le GPU : pour chaque couche, tous ses niveaux de mipmap
+    let mut data = Vec::new();
+    for layer in 0..layer_count {
+        let mut level = vec![255u8; tile_size * tile_size * 4];
+
+        if let Some((base_uv, size_uv)) = atlas_material.uv_map.get(&(layer as u16)) {
+            let x0 = (base_uv[0] * atlas_width as f32).round() as usize;
+            let y0 = (base_uv[1] * atlas_height as f32).round() as usize;
+            let w = ((size_uv[0] * atlas_width as f32).round() as usize).max(1);
+            let h = ((size_uv[1] * atlas_height as f32).round() as usize).max(1);
+
+            sample_tile(&mut level, tile_size, atlas_data, atlas_width, [x0, y0, w, h]);
+            if level.chunks_exact(4).all(|pixel| pixel[3] == 0) {
+                // Zone vide : image de l'atlas pas encore repackée avec cette texture, on lit sa source
+                let name = texture_names.get(&(layer as u16)).map_or("", |name| name.as_str());
+                let path = Path::new(BLOCK_TEXTURES_PATH).join(name);
+                match image::open(&path) {
+                    Ok(source) => {
+                        let source = source.to_rgba8();
+                        let (w, h) = (source.width() as usize, source.height() as usize);
+                        sample_tile(&mut level, tile_size, source.as_raw(), w, [0, 0, w, h]);
+                    }
+                    Err(e) => warn!("Texture {} absente de l'image de l'atlas et illisible ({})", path.display(), e),
+                }
+            }
+
+            // La couleur de ces textures vient du biome (teinte des sommets)
+            if BlockRegistry::global().is_biome_tinted(layer as u16) {
+                desaturate_rgba(&mut level);
+            }
+        }
+
+        let mut size = tile_size;
+        data.extend_from_slice(&level);
+        for _ in 1..mip_count {
+            level = downsample_rgba(&level, size);
+            size /= 2;
+            data.extend_from_slice(&level);
+        }
+    }
+
+    let mut image = Image::new(
+        Extent3d {
+            width: tile_size as u32,
+            height: tile_size as u32,
+            depth_or_array_layers: layer_count as u32,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    image.texture_descriptor.mip_level_count = mip_count as u32;
+    image.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+        address_mode_u: ImageAddressMode::Repeat,
+        address_mode_v: ImageAddressMode::Repeat,
+        mag_filter: ImageFilterMode::Linear,
+        min_filter: ImageFilterMode::Linear,
+        mipmap_filter: ImageFilterMode::Linear,
+        anisotropy_clamp: 8,
+        ..Default::default()
+    });
+
+    let array = texture_array.array.clone();
+    images.insert(&array, image);
+    texture_array.built = true;
+    info!("Tableau de textures des blocs construit : {} couches de {}px", layer_count, tile_size);
+}
+
+/// Copie dans `level` (une couche de `tile_size` pixels de côté) le rectangle `[x, y, w, h]` de
+/// l'image RGBA `source`, au plus proche si la texture n'a pas la taille d'une couche
+fn sample_tile(level: &mut [u8], tile_size: usize, source: &[u8], source_width: usize, [x0, y0, w, h]: [usize; 4]) {
+    let source_height = source.len() / 4 / source_width;
+    for y in 0..tile_size {
+        for x in 0..tile_size {
+            let sx = (x0 + x * w / tile_size).min(source_width - 1);
+            let sy = (y0 + y * h / tile_size).min(source_height - 1);
+            let src = (sy * source_width + sx) * 4;
+            let dst = (y * tile_size + x) * 4;
+            level[dst..dst + 4].copy_from_slice(&source[src..src + 4]);
+        }
+    }
+}
+
+/// Passe une texture en niveaux de gris, en ramenant sa luminance moyenne vers le clair
+/// pour que la teinte du biome donne sa couleur sans assombrir
+fn desaturate_rgba(pixels: &mut [u8]) {
+    const TARGET_LUMINANCE: f32 = 200.0;
+
+    let luminance = |p: &[u8]| 0.2126 * p[0] as f32 + 0.7152 * p[1] as f32 + 0.0722 * p[2] as f32;
+    let visible: Vec<f32> = pixels.chunks_exact(4).filter(|p| p[3] > 0).map(luminance).collect();
+    if visible.is_empty() {
+        return;
+    }
+    let mean = visible.iter().sum::<f32>() / visible.len() as f32;
+    let scale = TARGET_LUMINANCE / mean.max(1.0);
+
+    for pixel in pixels.chunks_exact_mut(4) {
+        let gray = (luminance(pixel) * scale).min(255.0) as u8;
+        pixel[..3].fill(gray);
+    }
+}
+
+/// Moyenne 2x2 d'une image RGBA carrée (filtrage dans l'espace sRGB, suffisant ici)
+fn downsample_rgba(source: &[u8], size: usize) -> Vec<u8> {
+    let half = (size / 2).max(1);
+    let mut result = vec![0u8; half * half * 4];
+    for y in 0..half {
+        for x in 0..half {
+            for c in 0..4 {
+                let texel = |dx: usize, dy: usize| {
+                    let sx = (x * 2 + dx).min(size - 1);
+                    let sy = (y * 2 + dy).min(size - 1);
+                    source[(sy * size + sx) * 4 + c] as u32
+                };
+                let sum = texel(0, 0) + texel(1, 0) + texel(0, 1) + texel(1, 1);
+                result[(y * half + x) * 4 + c] = (sum / 4) as u8;
+            }
+        }
+    }
+    result
+}
@@ -3,17 +3,8 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 use serde::Deserialize;
-use bevy::asset::{Assets, AssetServer, Handle};
-use bevy::pbr::StandardMaterial;
-use bevy::prelude::{default, Res, ResMut, Resource};
-use bevy::render::render_resource::{AsBindGroup, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType, BufferBindingType, BufferSize, Sampler, ShaderRef, ShaderStages, ShaderType};
-use bevy::sprite::Material2d;
-use bevy::window::PrimaryWindow;
-use bevy_mod_mipmap_generator::{generate_mipmaps, MipmapGeneratorPlugin};
-use bevy_pbr::MaterialPipeline;
-use crate::generation::chunk_generation_logic::ChunkGenerationPlugin;
-use crate::world::block::BlockType;
-
+use crate::render::voxel_material::{build_block_texture_array, update_voxel_lighting, BlockTextureArray, VoxelMaterial, VoxelParams};
+use crate::world::block::{BlockRegistry, RenderLayer};
 
 //lire le json
 #[derive(Deserialize, Debug)]
@@ -46,61 +37,89 @@ struct AtlasSize {
     h: f32,
 }
 
-fn filename_to_block_type(name: &str) -> Option<BlockType> {
-    match name {
-        "dirt.jpg" => Some(BlockType::Dirt),
-        "grass.jpg" => Some(BlockType::Grass),
-        "rock.jpg" => Some(BlockType::Rock),
-        "water.jpg" => Some(BlockType::Water),
-        "sand.jpg" => Some(BlockType::Sand),
-        _ => None,
-    }
-}
-
-
 //Load Texture
 #[derive(Resource,Clone)]
 pub struct TextureAtlasMaterial {
-    pub opaque_handle: Handle<StandardMaterial>,
-    pub water_handle: Handle<StandardMaterial>, // <- pour l’eau
-    pub uv_map: HashMap<BlockType, ([f32; 2], [f32; 2])>, // (base_uv, size_uv)
+    pub opaque_handle: Handle<VoxelMaterial>,
+    pub cutout_handle: Handle<VoxelMaterial>, // <- feuilles
+    pub translucent_handle: Handle<VoxelMaterial>, // <- eau, verre, glace
+    pub uv_map: HashMap<u16, ([f32; 2], [f32; 2])>, // identifiant de texture -> (base_uv, size_uv) dans l'atlas
+}
+
+impl TextureAtlasMaterial {
+    pub fn handle_for(&self, layer: RenderLayer) -> &Handle<VoxelMaterial> {
+        match layer {
+            RenderLayer::Opaque => &self.opaque_handle,
+            RenderLayer::Cutout => &self.cutout_handle,
+            RenderLayer::Translucent => &self.translucent_handle,
+        }
+    }
+
+    pub fn handles(&self) -> [&Handle<VoxelMaterial>; 3] {
+        RenderLayer::ALL.map(|layer| self.handle_for(layer))
+    }
 }
 
 
 pub struct TexturePlugin;
 impl Plugin for TexturePlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(MipmapGeneratorPlugin);
-        app.add_systems(Update, generate_mipmaps::<StandardMaterial>);  // Ajout du système générateur de mipmaps
+        app.add_plugins(MaterialPlugin::<VoxelMaterial>::default());
         app.add_systems(Startup, setup_texture_atlas);
+        app.add_systems(Update, build_block_texture_array);  // Découpe l'atlas en tableau de textures (avec mipmaps)
+        app.add_systems(Update, update_voxel_lighting);
     }
 }
 
 pub fn setup_texture_atlas(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+    block_registry: Res<BlockRegistry>,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<VoxelMaterial>>,
 ) {
     let texture_handle = asset_server.load("atlas_texture.png");
-    let normal_map_handle = asset_server.load("atlas_texture_normal.png");
 
-    let water_material = materials.add(StandardMaterial {
-        base_color_texture: Some(texture_handle.clone()), // Optionnel : tu peux le supprimer si tu veux un flat color
-        normal_map_texture: Some(normal_map_handle.clone()), // Optionnel
-        base_color: Color::srgba(0.11, 0.16, 0.26, 0.99), // Bleu transparent
+    // Rempli par build_block_texture_array une fois l'atlas chargé
+    let array_handle = images.reserve_handle();
+    commands.insert_resource(BlockTextureArray {
+        atlas: texture_handle,
+        array: array_handle.clone(),
+        built: false,
+    });
+
+    // La couleur propre à chaque bloc (bleu de l'eau...) est portée par les sommets
+    let translucent_material = materials.add(VoxelMaterial {
+        params: VoxelParams::with_tint(Vec4::ONE),
+        array_texture: array_handle.clone(),
         alpha_mode: AlphaMode::Blend,
-        unlit: false,
-        ..default()
     });
 
+    let cutout_material = materials.add(VoxelMaterial {
+        params: VoxelParams { alpha_cutoff: 0.5, ..VoxelParams::with_tint(Vec4::ONE) },
+        array_texture: array_handle.clone(),
+        alpha_mode: AlphaMode::Mask(0.5),
+    });
 
-    let standard_material = materials.add(StandardMaterial {
-        base_color_texture: Some(texture_handle.clone()),
-        normal_map_texture: Some(normal_map_handle.clone()),
-        perceptual_roughness: 1.0,
-        ..default()
+    let standard_material = materials.add(VoxelMaterial {
+        params: VoxelParams::with_tint(Vec4::ONE),
+        array_texture: array_handle,
+        alpha_mode: AlphaMode::Opaque,
     });
 
+    let uv_map = load_atlas_uv_map(&block_registry);
+
+    commands.insert_resource(TextureAtlasMaterial {
+        opaque_handle: standard_material,
+        cutout_handle: cutout_material,
+        translucent_handle: translucent_material,
+        uv_map,
+    });
+}
+
+/// Position (base_uv, size_uv) de chaque texture du registre dans l'atlas, lue dans
+/// `assets/atlas_texture.json`. N'a besoin ni du GPU ni d'un `App` (export hors jeu).
+pub fn load_atlas_uv_map(block_registry: &BlockRegistry) -> HashMap<u16, ([f32; 2], [f32; 2])> {
     let json_path = Path::new("assets/atlas_texture.json");
     let json_str = fs::read_to_string(json_path).expect("Impossible de lire spritesheet.json");
     let atlas_data: AtlasData = serde_json::from_str(&json_str).expect("JSON mal formé");
@@ -110,8 +129,8 @@ pub fn setup_texture_atlas(
 
     let mut uv_map = HashMap::new();
 
-    for (filename, frame_data) in atlas_data.frames.iter() {
-        if let Some(block_type) = filename_to_block_type(filename.as_str()) {
+    for (texture_id, filename) in block_registry.textures() {
+        if let Some(frame_data) = atlas_data.frames.get(filename) {
             let frame = &frame_data.frame;
 
             // On convertit les coordonnées pixels -> UV
@@ -120,15 +139,11 @@ pub fn setup_texture_atlas(
             let w = frame.w / atlas_width;
             let h = frame.h / atlas_height;
 
-            uv_map.insert(block_type, ([u, v], [w, h]));
+            uv_map.insert(texture_id, ([u, v], [w, h]));
+        } else {
+            warn!("Texture {} absente de l'atlas", filename);
         }
     }
 
-    commands.insert_resource(TextureAtlasMaterial {
-        opaque_handle: standard_material,
-        water_handle: water_material,
-        uv_map,
-    });
+    uv_map
 }
-
-
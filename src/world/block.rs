@@ -1,40 +1,350 @@
-#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug,Default)]
-pub enum BlockType {
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use bevy::prelude::Resource;
+use anyhow::Context;
+use serde::Deserialize;
+use crate::world::direction::Direction;
+use crate::world::block_model::{BlockModel, MODELS_PATH};
+
+pub const BLOCKS_PATH: &str = "assets/blocks.json";
+
+/// Identifiant numérique d'un bloc, tel que déclaré dans `assets/blocks.json`.
+/// Les constantes ci-dessous désignent les blocs utilisés directement par le code
+/// (génération, rendu de l'eau...). Tous les autres blocs n'existent que dans le registre.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct BlockType(pub u16);
+
+impl BlockType {
+    pub const AIR: BlockType = BlockType(0);
+    pub const GRASS: BlockType = BlockType(1);
+    pub const DIRT: BlockType = BlockType(2);
+    pub const ROCK: BlockType = BlockType(3);
+    pub const BRICK: BlockType = BlockType(4);
+    pub const WATER: BlockType = BlockType(5);
+    pub const SAND: BlockType = BlockType(6);
+
+    pub fn from_string(name: &str) -> Self {
+        BlockRegistry::global().by_name(name).unwrap_or(BlockType::AIR)
+    }
+
+    pub fn definition(&self) -> &'static BlockDefinition {
+        BlockRegistry::global().get(*self)
+    }
+}
+
+impl ToString for BlockType {
+    fn to_string(&self) -> String {
+        self.definition().name.clone()
+    }
+}
+
+/// Textures d'un bloc. Une face précise (`north`...) l'emporte sur `side`,
+/// qui l'emporte sur `all` ; `top` et `bottom` retombent directement sur `all`.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct BlockTextures {
+    pub all: Option<String>,
+    pub top: Option<String>,
+    pub bottom: Option<String>,
+    pub side: Option<String>,
+    pub north: Option<String>,
+    pub south: Option<String>,
+    pub east: Option<String>,
+    pub west: Option<String>,
+}
+
+impl BlockTextures {
+    pub fn for_direction(&self, direction: Direction) -> Option<&String> {
+        let face = match direction {
+            Direction::Up => return self.top.as_ref().or(self.all.as_ref()),
+            Direction::Down => return self.bottom.as_ref().or(self.all.as_ref()),
+            Direction::North => &self.north,
+            Direction::South => &self.south,
+            Direction::East => &self.east,
+            Direction::West => &self.west,
+        };
+        face.as_ref().or(self.side.as_ref()).or(self.all.as_ref())
+    }
+}
+
+/// Passe de rendu d'un bloc. Chaque couche a son propre mesh par section et son propre matériau.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RenderLayer {
+    /// Blocs pleins, sans transparence
     #[default]
-    Air,
+    Opaque,
+    /// Texture percée (feuilles) : chaque pixel est plein ou vide, les faces entre
+    /// deux blocs identiques restent dessinées
+    Cutout,
+    /// Semi-transparent (verre, glace, eau) : mélangé et trié d'arrière en avant,
+    /// sans faces entre deux blocs identiques
+    Translucent,
+}
+
+impl RenderLayer {
+    pub const ALL: [RenderLayer; 3] = [RenderLayer::Opaque, RenderLayer::Cutout, RenderLayer::Translucent];
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Couleur qui dépend du biome, multipliée à la teinte du bloc. Les textures concernées
+/// sont passées en niveaux de gris dans le tableau de textures.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum BiomeTint {
+    /// Dessus de l'herbe et plantes (colormap de l'herbe)
     Grass,
-    Dirt,
-    Rock,
-    Brick,
+    /// Feuillages (colormap des feuilles)
+    Foliage,
+    /// Couleur de l'eau du biome
     Water,
-    Sand
 }
 
-impl BlockType {
-    pub const VALUES: &'static [BlockType] = &[
-        BlockType::Air,
-        BlockType::Grass,
-        BlockType::Dirt,
-        BlockType::Rock,
-        BlockType::Water,
-        BlockType::Sand
-    ];
+impl BiomeTint {
+    pub const ALL: [BiomeTint; 3] = [BiomeTint::Grass, BiomeTint::Foliage, BiomeTint::Water];
 
-    pub fn from_string(name: &str) -> Self {
-        match name {
-            "minecraft:grass" => BlockType::Grass,
-            "minecraft:dirt" => BlockType::Dirt,
-            "minecraft:rock" => BlockType::Rock,
-            "minecraft:water" => BlockType::Water,
-            "minecraft:sand" => BlockType::Sand,
-            _ => BlockType::Air,
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+fn default_tint() -> [f32; 4] {
+    [1.0; 4]
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BlockDefinition {
+    pub id: u16,
+    pub name: String,
+    #[serde(default)]
+    pub textures: BlockTextures,
+    /// Atténuation de la lumière (0 = laisse tout passer, 15 = bloque tout)
+    #[serde(default)]
+    pub opacity: u8,
+    /// Les faces voisines restent visibles à travers ce bloc
+    #[serde(default)]
+    pub transparent: bool,
+    #[serde(default)]
+    pub solid: bool,
+    #[serde(default)]
+    pub fluid: bool,
+    #[serde(default)]
+    pub hardness: f32,
+    #[serde(default)]
+    pub light_emission: u8,
+    #[serde(default)]
+    pub render_layer: RenderLayer,
+    /// Couleur sRGB et opacité multipliées à la texture
+    #[serde(default = "default_tint")]
+    pub tint: [f32; 4],
+    /// Modèle JSON (chemin relatif à `assets/models`, par ex. "block/slab") ; None pour un cube plein
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Valeurs des variables de texture du modèle (`#side`...)
+    #[serde(default)]
+    pub model_textures: HashMap<String, String>,
+    /// Rotation du modèle autour de l'axe vertical, en degrés (multiple de 90)
+    #[serde(default)]
+    pub model_rotation: i32,
+    /// Teinte du biome. Sur un cube, l'herbe ne teinte que la face du dessus.
+    #[serde(default)]
+    pub biome_tint: Option<BiomeTint>,
+}
+
+impl BlockDefinition {
+    /// Teinte du biome de la face `direction` (None pour une face qui garde ses couleurs)
+    pub fn biome_tint_for(&self, direction: Direction) -> Option<BiomeTint> {
+        match self.biome_tint? {
+            BiomeTint::Grass if self.model.is_none() && direction != Direction::Up => None,
+            tint => Some(tint),
         }
     }
+
+    /// Cube plein qui cache entièrement les faces collées à lui
+    pub fn is_full_cube(&self) -> bool {
+        !self.transparent && self.model.is_none()
+    }
 }
 
-impl ToString for BlockType {
-    fn to_string(&self) -> String {
-        format!("minecraft:{:?}", self).to_lowercase()
+#[derive(Deserialize)]
+struct BlocksFile {
+    blocks: Vec<BlockDefinition>,
+}
+
+/// Registre de tous les blocs connus, chargé depuis `assets/blocks.json`.
+#[derive(Resource, Clone, Debug)]
+pub struct BlockRegistry {
+    definitions: Arc<Vec<BlockDefinition>>,
+    names: Arc<HashMap<String, BlockType>>,
+    /// Noms de fichiers de toutes les textures utilisées, indexés par identifiant de texture
+    textures: Arc<Vec<String>>,
+    /// Identifiant de texture de chaque face (ordre de `Direction::ALL`), par bloc
+    face_textures: Arc<Vec<[Option<u16>; 6]>>,
+    /// Modèle résolu des blocs qui ne sont pas des cubes, par bloc
+    models: Arc<Vec<Option<Arc<BlockModel>>>>,
+    /// Textures teintées par le biome, à passer en niveaux de gris
+    biome_tinted_textures: Arc<HashSet<u16>>,
+}
+
+static REGISTRY: OnceLock<BlockRegistry> = OnceLock::new();
+
+impl BlockRegistry {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let json_str = fs::read_to_string(path)?;
+        Self::from_json(&json_str, Path::new(MODELS_PATH))
+    }
+
+    /// `models_dir` est le dossier où chercher les modèles des blocs qui en déclarent un
+    pub fn from_json(json_str: &str, models_dir: &Path) -> anyhow::Result<Self> {
+        let file: BlocksFile = serde_json::from_str(json_str)?;
+
+        let max_id = file.blocks.iter().map(|b| b.id as usize).max().unwrap_or(0);
+        let mut slots: Vec<Option<BlockDefinition>> = vec![None; max_id + 1];
+        let mut names = HashMap::new();
+
+        for definition in file.blocks {
+            let id = definition.id as usize;
+            if slots[id].is_some() {
+                anyhow::bail!("Identifiant de bloc {} déclaré deux fois", id);
+            }
+            names.insert(definition.name.clone(), BlockType(definition.id));
+            slots[id] = Some(definition);
+        }
+
+        let definitions = slots
+            .into_iter()
+            .enumerate()
+            .map(|(id, slot)| slot.ok_or_else(|| anyhow::anyhow!("Identifiant de bloc {} manquant", id)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        if definitions[BlockType::AIR.0 as usize].name != "minecraft:air" {
+            anyhow::bail!("Le bloc 0 doit être minecraft:air");
+        }
+
+        let mut textures: Vec<String> = Vec::new();
+        let mut intern_texture = |name: &str| match textures.iter().position(|t| t == name) {
+            Some(index) => index as u16,
+            None => {
+                textures.push(name.to_string());
+                (textures.len() - 1) as u16
+            }
+        };
+
+        let face_textures: Vec<[Option<u16>; 6]> = definitions
+            .iter()
+            .map(|definition| {
+                Direction::ALL.map(|direction| {
+                    definition.textures.for_direction(direction).map(|name| intern_texture(name))
+                })
+            })
+            .collect();
+
+        let models = definitions
+            .iter()
+            .map(|definition| {
+                let Some(model) = &definition.model else {
+                    return Ok(None);
+                };
+                let model = BlockModel::load(
+                    models_dir,
+                    model,
+                    &definition.model_textures,
+                    definition.model_rotation,
+                    &mut intern_texture,
+                ).with_context(|| format!("Modèle du bloc {}", definition.name))?;
+                Ok(Some(Arc::new(model)))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let mut biome_tinted_textures = HashSet::new();
+        for (definition, faces) in definitions.iter().zip(&face_textures) {
+            for (direction, texture) in Direction::ALL.into_iter().zip(faces) {
+                if let (Some(_), Some(texture)) = (definition.biome_tint_for(direction), texture) {
+                    biome_tinted_textures.insert(*texture);
+                }
+            }
+        }
+        for (definition, model) in definitions.iter().zip(&models) {
+            if let (Some(_), Some(model)) = (definition.biome_tint, model) {
+                biome_tinted_textures.extend(model.quads.iter().map(|quad| quad.texture));
+            }
+        }
+
+        Ok(Self {
+            definitions: Arc::new(definitions),
+            names: Arc::new(names),
+            textures: Arc::new(textures),
+            face_textures: Arc::new(face_textures),
+            models: Arc::new(models),
+            biome_tinted_textures: Arc::new(biome_tinted_textures),
+        })
+    }
+
+    /// Registre partagé, chargé au premier accès. Utilisé par le code qui n'a pas
+    /// accès au `World` (tâches asynchrones de génération et de maillage).
+    pub fn global() -> &'static BlockRegistry {
+        REGISTRY.get_or_init(|| {
+            BlockRegistry::load(Path::new(BLOCKS_PATH)).expect("Impossible de lire blocks.json")
+        })
+    }
+
+    pub fn get(&self, block: BlockType) -> &BlockDefinition {
+        self.definitions
+            .get(block.0 as usize)
+            .unwrap_or(&self.definitions[BlockType::AIR.0 as usize])
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<BlockType> {
+        self.names.get(name).copied()
+    }
+
+    /// Identifiant de la texture affichée sur la face `direction` du bloc
+    pub fn face_texture(&self, block: BlockType, direction: Direction) -> Option<u16> {
+        self.face_textures
+            .get(block.0 as usize)
+            .and_then(|faces| faces[direction as usize])
+    }
+
+    /// Modèle du bloc, None pour un cube plein
+    pub fn model(&self, block: BlockType) -> Option<&BlockModel> {
+        self.models.get(block.0 as usize).and_then(|model| model.as_deref())
+    }
+
+    /// Vrai si la texture est teintée par le biome sur au moins une face
+    pub fn is_biome_tinted(&self, texture: u16) -> bool {
+        self.biome_tinted_textures.contains(&texture)
+    }
+
+    pub fn textures(&self) -> impl Iterator<Item = (u16, &String)> {
+        self.textures.iter().enumerate().map(|(id, name)| (id as u16, name))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (BlockType, &BlockDefinition)> {
+        self.definitions.iter().map(|d| (BlockType(d.id), d))
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-}
\ No newline at end of file
+    #[test]
+    fn constants_keep_their_registry_ids() {
+        let registry = BlockRegistry::global();
+        for (block, name) in [
+            (BlockType::AIR, "minecraft:air"),
+            (BlockType::GRASS, "minecraft:grass"),
+            (BlockType::DIRT, "minecraft:dirt"),
+            (BlockType::ROCK, "minecraft:rock"),
+            (BlockType::BRICK, "minecraft:brick"),
+            (BlockType::WATER, "minecraft:water"),
+            (BlockType::SAND, "minecraft:sand"),
+        ] {
+            assert_eq!(registry.by_name(name), Some(block), "{}", name);
+        }
+    }
+}
@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+use std::f32::consts::FRAC_PI_2;
+use std::fs;
+use std::path::Path;
+use anyhow::Context;
+use bevy::math::{Quat, Vec2, Vec3};
+use serde::Deserialize;
+use crate::world::direction::Direction;
+
+pub const MODELS_PATH: &str = "assets/models";
+
+/// Profondeur maximale de la chaîne de `parent`, pour éviter les boucles
+const MAX_PARENT_DEPTH: usize = 16;
+
+/// Modèle de bloc tel qu'écrit dans `assets/models`, dans l'esprit des modèles vanilla :
+/// un `parent` optionnel, des variables de texture (`"side": "#all"`) et des éléments (boîtes)
+#[derive(Deserialize, Debug, Clone, Default)]
+struct ModelFile {
+    parent: Option<String>,
+    #[serde(default)]
+    textures: HashMap<String, String>,
+    elements: Option<Vec<ModelElement>>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct ModelElement {
+    /// Coins de la boîte, en seizièmes de bloc
+    from: [f32; 3],
+    to: [f32; 3],
+    rotation: Option<ElementRotation>,
+    #[serde(default)]
+    faces: HashMap<FaceName, ModelFace>,
+    /// Boîte prise en compte par les collisions (faux pour les plantes en croix)
+    #[serde(default = "default_true")]
+    collision: bool,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct ElementRotation {
+    origin: [f32; 3],
+    axis: Axis,
+    /// Angle en degrés
+    angle: f32,
+    /// Étire l'élément pour qu'il garde la largeur du bloc une fois tourné (plantes en croix)
+    #[serde(default)]
+    rescale: bool,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct ModelFace {
+    /// [u1, v1, u2, v2] en seizièmes de texture ; déduit de la position si absent
+    uv: Option<[f32; 4]>,
+    texture: String,
+    /// Face masquée quand le voisin dans cette direction est un cube plein
+    cullface: Option<FaceName>,
+    /// Rotation de la texture, par pas de 90°
+    #[serde(default)]
+    rotation: u32,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+enum FaceName {
+    Down,
+    Up,
+    North,
+    South,
+    West,
+    East,
+}
+
+impl FaceName {
+    fn direction(self) -> Direction {
+        match self {
+            FaceName::Down => Direction::Down,
+            FaceName::Up => Direction::Up,
+            FaceName::North => Direction::North,
+            FaceName::South => Direction::South,
+            FaceName::West => Direction::West,
+            FaceName::East => Direction::East,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Face d'un modèle résolu, en coordonnées de bloc (0..1)
+#[derive(Debug, Clone)]
+pub struct ModelQuad {
+    /// Sommets dans le sens anti-horaire vu de l'extérieur de la face
+    pub positions: [Vec3; 4],
+    pub normal: Vec3,
+    /// UVs dans la texture (0..1)
+    pub uvs: [Vec2; 4],
+    pub texture: u16,
+    pub cullface: Option<Direction>,
+}
+
+/// Modèle prêt pour le mailleur : les textures sont des identifiants du registre,
+/// et les rotations (des éléments et du bloc) sont déjà appliquées
+#[derive(Debug, Clone, Default)]
+pub struct BlockModel {
+    pub quads: Vec<ModelQuad>,
+    /// Les 8 coins de chaque boîte de collision, en coordonnées de bloc
+    pub collision_boxes: Vec<[Vec3; 8]>,
+}
+
+impl BlockModel {
+    /// Charge `<models_dir>/<name>.json` et ses parents, résout les variables de texture
+    /// (celles du bloc l'emportent sur celles du modèle) et tourne le tout de
+    /// `rotation_y` degrés autour de l'axe vertical.
+    /// `intern_texture` donne l'identifiant de texture d'un nom de fichier.
+    pub fn load(
+        models_dir: &Path,
+        name: &str,
+        block_textures: &HashMap<String, String>,
+        rotation_y: i32,
+        mut intern_texture: impl FnMut(&str) -> u16,
+    ) -> anyhow::Result<Self> {
+        let file = load_with_parents(models_dir, name, 0)?;
+        let mut textures = file.textures;
+        textures.extend(block_textures.iter().map(|(k, v)| (k.clone(), v.clone())));
+
+        let quarter_turns = rotation_y.rem_euclid(360) / 90;
+        let block_rotation = Quat::from_rotation_y(-(quarter_turns as f32) * FRAC_PI_2);
+        let center = Vec3::splat(0.5);
+        let rotate_block = |p: Vec3| center + block_rotation * (p - center);
+
+        let mut model = BlockModel::default();
+        for element in file.elements.unwrap_or_default() {
+            let transform = element_transform(&element);
+            let to_block = |p: Vec3| rotate_block(transform(p) / 16.0);
+
+            let min = Vec3::from(element.from);
+            let max = Vec3::from(element.to);
+
+            for (face_name, face) in &element.faces {
+                let direction = face_name.direction();
+                let texture_name = resolve_texture(&textures, &face.texture)
+                    .with_context(|| format!("Texture {} introuvable dans le modèle {}", face.texture, name))?;
+
+                let positions = box_face(min, max, direction).map(to_block);
+                let normal = ((positions[1] - positions[0]).cross(positions[3] - positions[0])).normalize_or_zero();
+
+                let [u1, v1, u2, v2] = face.uv.unwrap_or_else(|| default_uv(min, max, direction));
+                let mut uvs = [
+                    Vec2::new(u1, v2),
+                    Vec2::new(u2, v2),
+                    Vec2::new(u2, v1),
+                    Vec2::new(u1, v1),
+                ].map(|uv| uv / 16.0);
+                uvs.rotate_left((face.rotation / 90 % 4) as usize);
+
+                model.quads.push(ModelQuad {
+                    positions,
+                    normal,
+                    uvs,
+                    texture: intern_texture(&texture_name),
+                    cullface: face.cullface.map(|c| rotate_direction_y(c.direction(), quarter_turns)),
+                });
+            }
+
+            if element.collision {
+                let corners = std::array::from_fn(|i| {
+                    Vec3::new(
+                        if i & 1 == 0 { min.x } else { max.x },
+                        if i & 2 == 0 { min.y } else { max.y },
+                        if i & 4 == 0 { min.z } else { max.z },
+                    )
+                });
+                model.collision_boxes.push(corners.map(to_block));
+            }
+        }
+
+        Ok(model)
+    }
+}
+
+fn load_with_parents(models_dir: &Path, name: &str, depth: usize) -> anyhow::Result<ModelFile> {
+    if depth > MAX_PARENT_DEPTH {
+        anyhow::bail!("Chaîne de parents trop longue pour le modèle {}", name);
+    }
+    let path = models_dir.join(format!("{}.json", name));
+    let json_str = fs::read_to_string(&path)
+        .with_context(|| format!("Impossible de lire le modèle {}", path.display()))?;
+    let mut file: ModelFile = serde_json::from_str(&json_str)
+        .with_context(|| format!("Modèle {} mal formé", path.display()))?;
+
+    if let Some(parent) = file.parent.take() {
+        let parent = load_with_parents(models_dir, &parent, depth + 1)?;
+        let mut textures = parent.textures;
+        textures.extend(file.textures);
+        file.textures = textures;
+        if file.elements.is_none() {
+            file.elements = parent.elements;
+        }
+    }
+    Ok(file)
+}
+
+/// Suit les références `#variable` jusqu'à un nom de fichier
+fn resolve_texture(textures: &HashMap<String, String>, reference: &str) -> Option<String> {
+    let mut current = reference;
+    for _ in 0..MAX_PARENT_DEPTH {
+        match current.strip_prefix('#') {
+            Some(variable) => current = textures.get(variable)?.as_str(),
+            None => return Some(current.to_string()),
+        }
+    }
+    None
+}
+
+/// Rotation (et étirement éventuel) d'un élément autour de son origine, en seizièmes de bloc
+fn element_transform(element: &ModelElement) -> impl Fn(Vec3) -> Vec3 {
+    let (origin, rotation, scale) = match &element.rotation {
+        Some(rotation) => {
+            let angle = rotation.angle.to_radians();
+            let (axis, stretched) = match rotation.axis {
+                Axis::X => (Vec3::X, Vec3::new(0.0, 1.0, 1.0)),
+                Axis::Y => (Vec3::Y, Vec3::new(1.0, 0.0, 1.0)),
+                Axis::Z => (Vec3::Z, Vec3::new(1.0, 1.0, 0.0)),
+            };
+            let scale = if rotation.rescale && angle.cos().abs() > f32::EPSILON {
+                Vec3::ONE + stretched * (1.0 / angle.cos().abs() - 1.0)
+            } else {
+                Vec3::ONE
+            };
+            (Vec3::from(rotation.origin), Quat::from_axis_angle(axis, angle), scale)
+        }
+        None => (Vec3::ZERO, Quat::IDENTITY, Vec3::ONE),
+    };
+    move |p: Vec3| origin + scale * (rotation * (p - origin))
+}
+
+/// Sommets d'une face de la boîte, dans le même ordre que `quad_corners` pour les cubes
+fn box_face(min: Vec3, max: Vec3, direction: Direction) -> [Vec3; 4] {
+    let (x0, y0, z0) = (min.x, min.y, min.z);
+    let (x1, y1, z1) = (max.x, max.y, max.z);
+    let v = Vec3::new;
+    match direction {
+        Direction::Up => [v(x0, y1, z1), v(x1, y1, z1), v(x1, y1, z0), v(x0, y1, z0)],
+        Direction::Down => [v(x0, y0, z0), v(x1, y0, z0), v(x1, y0, z1), v(x0, y0, z1)],
+        Direction::North => [v(x1, y0, z0), v(x0, y0, z0), v(x0, y1, z0), v(x1, y1, z0)],
+        Direction::South => [v(x0, y0, z1), v(x1, y0, z1), v(x1, y1, z1), v(x0, y1, z1)],
+        Direction::West => [v(x0, y0, z0), v(x0, y0, z1), v(x0, y1, z1), v(x0, y1, z0)],
+        Direction::East => [v(x1, y0, z1), v(x1, y0, z0), v(x1, y1, z0), v(x1, y1, z1)],
+    }
+}
+
+/// UVs vanilla par défaut : la partie de la texture qui correspond à la position de la face
+fn default_uv(min: Vec3, max: Vec3, direction: Direction) -> [f32; 4] {
+    match direction {
+        Direction::Up => [min.x, min.z, max.x, max.z],
+        Direction::Down => [min.x, 16.0 - max.z, max.x, 16.0 - min.z],
+        Direction::North => [16.0 - max.x, 16.0 - max.y, 16.0 - min.x, 16.0 - min.y],
+        Direction::South => [min.x, 16.0 - max.y, max.x, 16.0 - min.y],
+        Direction::West => [min.z, 16.0 - max.y, max.z, 16.0 - min.y],
+        Direction::East => [16.0 - max.z, 16.0 - max.y, 16.0 - min.z, 16.0 - min.y],
+    }
+}
+
+/// Direction après `quarter_turns` quarts de tour dans le sens horaire vu du dessus
+fn rotate_direction_y(direction: Direction, quarter_turns: i32) -> Direction {
+    let mut direction = direction;
+    for _ in 0..quarter_turns {
+        direction = match direction {
+            Direction::North => Direction::East,
+            Direction::East => Direction::South,
+            Direction::South => Direction::West,
+            Direction::West => Direction::North,
+            vertical => vertical,
+        };
+    }
+    direction
+}
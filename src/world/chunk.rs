@@ -1,19 +1,99 @@
 use crate::constants::{CHUNK_SIZE, SECTION_HEIGHT};
 use crate::world::block::BlockType;
 
+/// Taille d'un tableau de lumière : un quartet (0..=15) par bloc de la section
+pub const LIGHT_ARRAY_SIZE: usize = CHUNK_SIZE * CHUNK_SIZE * SECTION_HEIGHT / 2;
+
 #[derive(Debug, Clone)]
 pub struct ChunkSection {
     pub y: i8,
     pub blocks: Vec<u8>, // index palette
-    pub palette: Vec<String>,
+    /// Noms des blocs tels que sauvegardés (un nom inconnu du registre est gardé tel quel)
+    palette: Vec<String>,
+    /// Palette résolue une fois pour toutes, lue à chaque accès à un bloc
+    palette_blocks: Vec<BlockType>,
+    pub sky_light: Vec<u8>,   // quartets, même indexation que blocks
+    pub block_light: Vec<u8>, // quartets, même indexation que blocks
+}
+
+fn get_nibble(array: &[u8], index: usize) -> u8 {
+    let byte = array[index / 2];
+    if index % 2 == 0 { byte & 0x0F } else { byte >> 4 }
+}
+
+fn set_nibble(array: &mut [u8], index: usize, value: u8) {
+    let byte = &mut array[index / 2];
+    if index % 2 == 0 {
+        *byte = (*byte & 0xF0) | (value & 0x0F);
+    } else {
+        *byte = (*byte & 0x0F) | ((value & 0x0F) << 4);
+    }
 }
 
 impl ChunkSection {
+    pub fn new(y: i8) -> Self {
+        ChunkSection {
+            y,
+            blocks: vec![0; CHUNK_SIZE * CHUNK_SIZE * SECTION_HEIGHT],
+            palette: vec![],
+            palette_blocks: vec![],
+            sky_light: vec![0; LIGHT_ARRAY_SIZE],
+            block_light: vec![0; LIGHT_ARRAY_SIZE],
+        }
+    }
+
+    pub fn palette(&self) -> &[String] {
+        &self.palette
+    }
+
+    pub fn palette_blocks(&self) -> &[BlockType] {
+        &self.palette_blocks
+    }
+
+    /// Remplace la palette ; les noms inconnus du registre se lisent comme de l'air
+    pub fn set_palette(&mut self, palette: Vec<String>) {
+        self.palette_blocks = palette.iter().map(|name| BlockType::from_string(name)).collect();
+        self.palette = palette;
+    }
+
+    /// Indice du bloc dans la palette, ajouté s'il n'y est pas encore. La recherche se fait par nom :
+    /// une entrée inconnue, lue comme de l'air, n'est jamais réutilisée pour un autre bloc.
+    pub fn palette_index(&mut self, block: BlockType) -> u8 {
+        let name = &block.definition().name;
+        match self.palette.iter().position(|entry| entry == name) {
+            Some(index) => index as u8,
+            None => {
+                self.palette.push(block.to_string());
+                self.palette_blocks.push(block);
+                (self.palette_blocks.len() - 1) as u8
+            }
+        }
+    }
+
     pub fn get_block(&self, x: usize, y: usize, z: usize) -> BlockType {
         let index = (y * 16 + z) * 16 + x;
-        let palette_index = self.blocks[index];
-        let block_name = &self.palette[palette_index as usize];
-        BlockType::from_string(block_name)
+        self.palette_blocks[self.blocks[index] as usize]
+    }
+
+    pub fn set_block(&mut self, x: usize, y: usize, z: usize, block: BlockType) {
+        let index = (y * 16 + z) * 16 + x;
+        self.blocks[index] = self.palette_index(block);
+    }
+
+    pub fn get_sky_light(&self, x: usize, y: usize, z: usize) -> u8 {
+        get_nibble(&self.sky_light, (y * 16 + z) * 16 + x)
+    }
+
+    pub fn set_sky_light(&mut self, x: usize, y: usize, z: usize, value: u8) {
+        set_nibble(&mut self.sky_light, (y * 16 + z) * 16 + x, value)
+    }
+
+    pub fn get_block_light(&self, x: usize, y: usize, z: usize) -> u8 {
+        get_nibble(&self.block_light, (y * 16 + z) * 16 + x)
+    }
+
+    pub fn set_block_light(&mut self, x: usize, y: usize, z: usize, value: u8) {
+        set_nibble(&mut self.block_light, (y * 16 + z) * 16 + x, value)
     }
 }
 
@@ -33,19 +113,63 @@ impl Chunk {
             sections: vec![],
         }
     }
-    pub fn get_block_at(&self, x: usize, y: usize, z: usize) -> BlockType {
-        let section_y = (y / 16) as i8;
-        let local_y = y % 16;
+    /// y est la hauteur mondiale (peut être négative si le monde descend sous 0)
+    pub fn get_block_at(&self, x: usize, y: isize, z: usize) -> BlockType {
+        let section_y = y.div_euclid(16) as i8;
+        let local_y = y.rem_euclid(16) as usize;
 
         if let Some(section) = self.sections.iter().find(|s| s.y == section_y) {
             let index = local_y * 16 * 16 + z * 16 + x;
             if let Some(&block_index) = section.blocks.get(index) {
-                if let Some(block_str) = section.palette.get(block_index as usize) {
-                    return BlockType::from_string(block_str);
+                if let Some(&block) = section.palette_blocks.get(block_index as usize) {
+                    return block;
                 }
             }
         }
 
-        BlockType::Air
+        BlockType::AIR
+    }
+
+    pub fn section(&self, section_y: i8) -> Option<&ChunkSection> {
+        self.sections.iter().find(|s| s.y == section_y)
     }
-}
\ No newline at end of file
+
+    pub fn section_mut(&mut self, section_y: i8) -> Option<&mut ChunkSection> {
+        self.sections.iter_mut().find(|s| s.y == section_y)
+    }
+
+    /// Lumière du ciel aux coordonnées locales. Une section absente est en plein jour.
+    pub fn get_sky_light_at(&self, x: usize, y: isize, z: usize) -> u8 {
+        match self.section(y.div_euclid(16) as i8) {
+            Some(section) => section.get_sky_light(x, y.rem_euclid(16) as usize, z),
+            None => 15,
+        }
+    }
+
+    pub fn get_block_light_at(&self, x: usize, y: isize, z: usize) -> u8 {
+        match self.section(y.div_euclid(16) as i8) {
+            Some(section) => section.get_block_light(x, y.rem_euclid(16) as usize, z),
+            None => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_palette_names_read_as_air_and_are_kept() {
+        let mut section = ChunkSection::new(0);
+        section.set_palette(vec!["mod:unknown".to_string(), BlockType::ROCK.to_string()]);
+        section.blocks[1] = 1;
+
+        assert_eq!(section.get_block(0, 0, 0), BlockType::AIR);
+        assert_eq!(section.get_block(1, 0, 0), BlockType::ROCK);
+        assert_eq!(section.palette()[0], "mod:unknown");
+        // Poser de l'air ajoute une vraie entrée plutôt que de réutiliser le nom inconnu
+        section.set_block(2, 0, 0, BlockType::AIR);
+        assert_eq!(section.palette().len(), 3);
+        assert_eq!(section.get_block(2, 0, 0), BlockType::AIR);
+    }
+}
@@ -1,20 +1,23 @@
-use std::sync::{Arc, Mutex};
 use bevy::app::{App, Plugin, Update};
-use bevy::math::{Affine3A, IVec2};
-use bevy::prelude::{Camera, Camera3d, Commands, Component, Entity, Event, EventReader, EventWriter, GlobalTransform, Query, Res, ResMut, Resource, Transform, Visibility, With};
+use bevy::math::IVec2;
+use bevy::log::info;
+use bevy::input::ButtonInput;
+use std::collections::HashMap;
+use bevy::prelude::{Assets, Camera3d, Commands, Component, DetectChanges, Entity, EventWriter, GlobalTransform, IntoScheduleConfigs, KeyCode, Mesh, Mesh3d, Query, RemovedComponents, Res, ResMut, Resource, Vec3, Visibility, With};
 use bevy::render::primitives::Frustum;
-use bevy::tasks::{AsyncComputeTaskPool, Task};
-use futures::FutureExt;
-use crate::constants::{CHUNK_SIZE, SECTION_HEIGHT, VIEW_DISTANCE, WORLD_HEIGHT, WORLD_SIZE};
+use crate::constants::CHUNK_SIZE;
 use crate::player::Player;
-use crate::world::chunk::Chunk;
-use crate::render::chunk_loadings_mesh_logic::ChunkToUpdateEvent;
-use crate::world::load_save_chunk::{ToLoadChunkEvent, WorldData};
+use crate::render::chunk_loadings_mesh_logic::{ChunkMeshTasks, ChunkToUpdateEvent, SectionToUpdateEvent};
+use crate::render::lod::{LodSettings, MAX_LOD};
+use crate::world::chunk_queue::{ChunkJobKind, ChunkWorkQueue};
+use crate::world::chunk_state::{ChunkStates, ChunkStatus};
+use crate::world::load_save_chunk::{ChunkSaves, ToLoadChunkEvent, WorldData};
+use crate::world::world_config::WorldConfig;
 
 // --- RESOURCES ---
 #[derive(Resource)]
-struct PlayerChunk {
-    current_chunk: IVec2,
+pub struct PlayerChunk {
+    pub current_chunk: IVec2,
 }
 
 impl Default for PlayerChunk {
@@ -25,8 +28,146 @@ impl Default for PlayerChunk {
     }
 }
 
-#[derive(Component)]
-struct LoadingChunkTask(Task<anyhow::Result<()>>);
+/// Rayons, en chunks, modifiables en jeu. Les chunks sont chargés jusqu'au plus grand des trois.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct ViewDistance {
+    /// Collisions des blocs construites autour des corps physiques
+    pub simulation: i32,
+    /// Chunks maillés en pleine résolution
+    pub meshing: i32,
+    /// Chunks chargés et affichés en LOD au-delà du rayon de maillage
+    pub far_lod: i32,
+}
+
+/// Rayon de chargement maximal accepté par les réglages
+const MAX_VIEW_DISTANCE: i32 = 64;
+
+/// Un des trois rayons de `ViewDistance`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViewRadius {
+    Simulation,
+    Meshing,
+    FarLod,
+}
+
+impl ViewDistance {
+    pub fn from_config(config: &WorldConfig) -> Self {
+        Self {
+            simulation: config.simulation_distance,
+            meshing: config.meshing_distance.min(config.view_distance),
+            far_lod: config.view_distance,
+        }
+    }
+
+    /// Change un rayon de `step` chunks. Le rayon de maillage reste dans le rayon lointain :
+    /// agrandir l'un au-delà de l'autre entraîne l'autre avec lui.
+    pub fn adjust(&mut self, radius: ViewRadius, step: i32) {
+        match radius {
+            ViewRadius::Simulation => self.simulation = (self.simulation + step).clamp(0, MAX_VIEW_DISTANCE),
+            ViewRadius::Meshing => {
+                self.meshing = (self.meshing + step).clamp(0, MAX_VIEW_DISTANCE);
+                self.far_lod = self.far_lod.max(self.meshing);
+            }
+            ViewRadius::FarLod => {
+                self.far_lod = (self.far_lod + step).clamp(2, MAX_VIEW_DISTANCE);
+                self.meshing = self.meshing.min(self.far_lod);
+            }
+        }
+    }
+
+    pub fn load_radius(&self) -> i32 {
+        self.simulation.max(self.meshing).max(self.far_lod)
+    }
+
+    /// Distances de passage aux niveaux de LOD : le niveau 1 commence au rayon de maillage,
+    /// les suivants se répartissent jusqu'au rayon lointain
+    pub fn lod_distances(&self) -> [i32; MAX_LOD as usize] {
+        let span = (self.far_lod - self.meshing).max(0);
+        std::array::from_fn(|level| self.meshing + span * level as i32 / MAX_LOD as i32)
+    }
+}
+
+/// Travail évité parce que le chunk est sorti du rayon de chargement avant d'être prêt
+#[derive(Resource, Default, Debug, Clone, Copy)]
+pub struct ChunkWorkStats {
+    /// Demandes retirées des files avant d'avoir commencé
+    pub loads_dropped: usize,
+    pub generations_dropped: usize,
+    /// Tâches terminées dont le résultat a été jeté
+    pub loads_discarded: usize,
+    pub generations_discarded: usize,
+    /// Tâches de maillage annulées en cours de route
+    pub meshes_cancelled: usize,
+}
+
+/// Entité qui garde chargés les chunks dans un cercle de `radius` chunks autour d'elle
+/// (joueurs, caméras de spectateur, zone d'apparition, machines...)
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct ChunkLoader {
+    pub radius: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct LoaderArea {
+    center: IVec2,
+    radius: i32,
+}
+
+impl LoaderArea {
+    fn chunks<'a>(&self, config: &'a WorldConfig) -> impl Iterator<Item = (i32, i32)> + 'a {
+        let LoaderArea { center, radius } = *self;
+        (-radius..=radius)
+            .flat_map(move |dx| (-radius..=radius).map(move |dz| (dx, dz)))
+            .filter(move |&(dx, dz)| dx * dx + dz * dz <= radius * radius)
+            .map(move |(dx, dz)| (center.x + dx, center.y + dz))
+            .filter(move |&(x, z)| config.contains_chunk(x, z))
+    }
+}
+
+/// Chunks modifiés pendant une mise à jour des tickets, avec leur état d'avant (voulu ou non)
+#[derive(Default)]
+struct TicketChanges {
+    touched: HashMap<(i32, i32), bool>,
+}
+
+/// Nombre de chargeurs qui veulent chaque chunk. Un chunk est chargé au premier ticket
+/// et déchargé quand le dernier est rendu : l'ensemble chargé est l'union des cercles des chargeurs.
+#[derive(Resource, Default)]
+pub struct ChunkTickets {
+    counts: HashMap<(i32, i32), u32>,
+    loaders: HashMap<Entity, LoaderArea>,
+}
+
+impl ChunkTickets {
+    pub fn is_wanted(&self, x: i32, z: i32) -> bool {
+        self.counts.contains_key(&(x, z))
+    }
+
+    pub fn loader_count(&self, x: i32, z: i32) -> u32 {
+        self.counts.get(&(x, z)).copied().unwrap_or(0)
+    }
+
+    fn acquire(&mut self, area: LoaderArea, config: &WorldConfig, changes: &mut TicketChanges) {
+        for pos in area.chunks(config) {
+            let count = self.counts.entry(pos).or_insert(0);
+            changes.touched.entry(pos).or_insert(*count > 0);
+            *count += 1;
+        }
+    }
+
+    fn release(&mut self, area: LoaderArea, config: &WorldConfig, changes: &mut TicketChanges) {
+        for pos in area.chunks(config) {
+            let Some(count) = self.counts.get_mut(&pos) else {
+                continue;
+            };
+            changes.touched.entry(pos).or_insert(true);
+            *count -= 1;
+            if *count == 0 {
+                self.counts.remove(&pos);
+            }
+        }
+    }
+}
 
 
 
@@ -37,70 +178,239 @@ pub struct ChunkLoadingsPlugin;
 impl Plugin for ChunkLoadingsPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<PlayerChunk>();
+        let view_distance = ViewDistance::from_config(app.world().resource::<WorldConfig>());
+        app.insert_resource(view_distance);
+        app.init_resource::<ChunkWorkStats>();
+        app.init_resource::<ChunkTickets>();
         app.add_event::<ToLoadChunkEvent>();
-        app.add_systems(Update, loading_and_unloading_chunks);
+        app.add_systems(Update, (
+            adjust_view_distance,
+            apply_view_distance,
+            track_player_chunk,
+            sync_player_loaders,
+            loading_and_unloading_chunks,
+            drop_out_of_range_work,
+        ).chain());
         app.add_systems(Update, update_visible_sessions);
         app.add_event::<ChunkToUpdateEvent>();
     }
 }
 
+/// Pavé numérique + et - : agrandit ou réduit le rayon lointain, le rayon de maillage avec Maj,
+/// le rayon de simulation avec Ctrl. Les autres systèmes suivent tout changement de `ViewDistance`.
+fn adjust_view_distance(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut view_distance: ResMut<ViewDistance>,
+) {
+    let step = if keys.just_pressed(KeyCode::NumpadAdd) {
+        1
+    } else if keys.just_pressed(KeyCode::NumpadSubtract) {
+        -1
+    } else {
+        return;
+    };
+
+    if keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        view_distance.adjust(ViewRadius::Simulation, step);
+    } else if keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        view_distance.adjust(ViewRadius::Meshing, 2 * step);
+    } else {
+        view_distance.adjust(ViewRadius::FarLod, 2 * step);
+    }
+    info!("Distance de vue : {:?}", *view_distance);
+}
+
+/// Répercute un changement des rayons sur les niveaux de détail
+fn apply_view_distance(
+    view_distance: Res<ViewDistance>,
+    mut lod_settings: ResMut<LodSettings>,
+) {
+    if view_distance.is_changed() {
+        lod_settings.distances = view_distance.lod_distances();
+    }
+}
+
+/// Suit le chunk du joueur (le premier, s'il y en a plusieurs) pour les niveaux de détail
+/// et les priorités de chargement
+fn track_player_chunk(
+    mut player_chunk: ResMut<PlayerChunk>,
+    player_query: Query<&GlobalTransform, With<Player>>,
+) {
+    let Some(player) = player_query.iter().next() else {
+        return;
+    };
+    let new_chunk = chunk_of(player.translation());
+    if new_chunk != player_chunk.current_chunk {
+        player_chunk.current_chunk = new_chunk;
+    }
+}
+
+/// Les joueurs chargent jusqu'au rayon de chargement réglé en jeu
+fn sync_player_loaders(
+    view_distance: Res<ViewDistance>,
+    mut loaders: Query<&mut ChunkLoader, With<Player>>,
+) {
+    let radius = view_distance.load_radius();
+    for mut loader in loaders.iter_mut() {
+        if loader.radius != radius {
+            loader.radius = radius;
+        }
+    }
+}
+
+/// Met à jour les tickets des chargeurs qui ont bougé, changé de rayon ou disparu,
+/// puis charge les chunks qui ont gagné leur premier ticket et décharge ceux qui ont perdu le dernier
 fn loading_and_unloading_chunks(
     mut commands: Commands,
-    mut player_chunk: ResMut<PlayerChunk>,
-    player_query: Query<&Transform, With<Player>>,
+    loaders: Query<(Entity, &GlobalTransform, &ChunkLoader)>,
+    mut removed_loaders: RemovedComponents<ChunkLoader>,
+    mut tickets: ResMut<ChunkTickets>,
+    mut states: ResMut<ChunkStates>,
     mut world_data: ResMut<WorldData>,
+    mut saves: ResMut<ChunkSaves>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mesh_handles: Query<&Mesh3d>,
     mut load_events: EventWriter<ToLoadChunkEvent>,
+    mut section_events: EventWriter<SectionToUpdateEvent>,
+    config: Res<WorldConfig>,
 ) {
-    let player_pos = player_query.single().unwrap().translation;
-    let new_chunk = IVec2::new(
-        (player_pos.x / CHUNK_SIZE as f32).floor() as i32,
-        (player_pos.z / CHUNK_SIZE as f32).floor() as i32,
-    );
+    let config = config.into_inner();
+    let mut changes = TicketChanges::default();
 
-    if new_chunk == player_chunk.current_chunk {
-        return;
+    for entity in removed_loaders.read() {
+        if let Some(area) = tickets.loaders.remove(&entity) {
+            tickets.release(area, config, &mut changes);
+        }
     }
-    player_chunk.current_chunk = new_chunk;
 
-    // Load new visible chunks
-    let half_world = (WORLD_SIZE / 2) as i32;
+    for (entity, transform, loader) in loaders.iter() {
+        let area = LoaderArea { center: chunk_of(transform.translation()), radius: loader.radius };
+        let previous = tickets.loaders.get(&entity).copied();
+        if previous == Some(area) {
+            continue;
+        }
+        if let Some(previous) = previous {
+            tickets.release(previous, config, &mut changes);
+        }
+        tickets.acquire(area, config, &mut changes);
+        tickets.loaders.insert(entity, area);
+    }
 
-    for x in -VIEW_DISTANCE..=VIEW_DISTANCE {
-        for z in -VIEW_DISTANCE..=VIEW_DISTANCE {
-            let pos = new_chunk + IVec2::new(x, z);
+    for (pos, was_wanted) in changes.touched {
+        let wanted = tickets.is_wanted(pos.0, pos.1);
+        if wanted && !was_wanted {
+            // Redemandé pendant sa sauvegarde : le chargement reprendra la copie en attente
+            let requested = if states.is(pos.0, pos.1, ChunkStatus::Saving) {
+                states.set(pos.0, pos.1, ChunkStatus::Requested)
+            } else {
+                states.request(pos.0, pos.1)
+            };
+            if requested {
+                load_events.write(ToLoadChunkEvent { x: pos.0, z: pos.1 });
+            }
+        } else if !wanted && was_wanted {
+            unload_chunk(&mut commands, config, &mut world_data, &mut saves, &mut meshes, &mesh_handles, &mut states, &mut section_events, pos);
+        }
+    }
+}
 
-            if (-half_world..half_world).contains(&pos.x) && (-half_world..half_world).contains(&pos.y) {
-                if !world_data.chunks_loaded.contains_key(&(pos.x, pos.y)) {
-                    load_events.write(ToLoadChunkEvent { x: pos.x, z: pos.y });
-                }
+/// Décharge un chunk quelle que soit son étape : supprime les entités de ses sections et leurs meshes,
+/// lance sa sauvegarde en tâche de fond s'il a été modifié, puis l'oublie. Un chunk sauvegardé reste à l'étape Saving
+/// jusqu'à la fin de l'écriture (voir `collect_finished_saves`). Les sections des voisins chargés sont remaillées,
+/// leurs faces de bordure n'étant plus cachées par ce chunk. Un chunk pas encore chargé n'a que son étape à oublier ;
+/// son travail en attente est retiré par `drop_out_of_range_work`.
+fn unload_chunk(
+    commands: &mut Commands,
+    config: &WorldConfig,
+    world_data: &mut WorldData,
+    saves: &mut ChunkSaves,
+    meshes: &mut Assets<Mesh>,
+    mesh_handles: &Query<&Mesh3d>,
+    states: &mut ChunkStates,
+    section_events: &mut EventWriter<SectionToUpdateEvent>,
+    pos: (i32, i32),
+) {
+    let (x, z) = pos;
+    let was_loaded = states.status(x, z).is_some_and(ChunkStatus::is_loaded);
+    if !states.set(x, z, ChunkStatus::Unloading) {
+        return;
+    }
+
+    for section in 0..config.section_count() {
+        let Some(entities) = world_data.chunks_sections_meshes.remove(&(x, z, section as i32)) else {
+            continue;
+        };
+        for (entity, _) in entities {
+            if let Ok(mesh) = mesh_handles.get(entity) {
+                meshes.remove(&mesh.0);
             }
+            commands.entity(entity).despawn();
         }
     }
 
-    // Unload distant chunks
-    let chunks_to_unload: Vec<(i32, i32)> = world_data.chunks_loaded.iter().filter_map(|(&pos, _)| {
-            if (IVec2::new(pos.0, pos.1) - new_chunk).abs().max_element() > VIEW_DISTANCE {
-                Some(pos)
-            } else {
-                None
+    if was_loaded {
+        let min_section = config.min_section() as i32;
+        for (nx, nz) in [(x - 1, z), (x + 1, z), (x, z - 1), (x, z + 1)] {
+            if !states.status(nx, nz).is_some_and(ChunkStatus::is_loaded) {
+                continue;
             }
-    }).collect();
-
-    // Étape 2 : Décharger ces chunks et les retirer de chunks_loaded
-    for pos in chunks_to_unload {
-        for section in 0..WORLD_HEIGHT / SECTION_HEIGHT{
-            if let Some(entity) = world_data.chunks_sections_meshes.get(&(pos.0, pos.1, section as i32)) {
-                for (et,_) in entity {
-                    commands.entity(*et).despawn();
-                }
-                world_data.chunks_sections_meshes.remove(&(pos.0, pos.1, section as i32));
-                world_data.chunks_loaded.remove(&pos);
+            for section_y in min_section..min_section + config.section_count() as i32 {
+                section_events.write(SectionToUpdateEvent { x: nx, z: nz, section_y: section_y as i8 });
             }
         }
+    }
 
+    let chunk = world_data.chunks_loaded.remove(&pos);
+    match chunk.filter(|_| states.is_modified(x, z)) {
+        Some(chunk) => {
+            states.set(x, z, ChunkStatus::Saving);
+            saves.save(chunk);
+        }
+        None => {
+            states.remove(x, z);
+        }
     }
 }
 
+fn chunk_of(position: Vec3) -> IVec2 {
+    IVec2::new(
+        (position.x / CHUNK_SIZE as f32).floor() as i32,
+        (position.z / CHUNK_SIZE as f32).floor() as i32,
+    )
+}
+
+
+/// Quand les tickets changent, vide les files de chargement, de génération et de maillage
+/// des chunks qu'aucun chargeur ne veut plus, et annule leurs maillages en cours. Les chargements et générations
+/// déjà lancés ne peuvent pas être interrompus : leur résultat est jeté à l'arrivée.
+fn drop_out_of_range_work(
+    tickets: Res<ChunkTickets>,
+    mut work_queue: ResMut<ChunkWorkQueue>,
+    mut mesh_tasks: ResMut<ChunkMeshTasks>,
+    mut stats: ResMut<ChunkWorkStats>,
+) {
+    if !tickets.is_changed() {
+        return;
+    }
+    let in_range = |x: i32, z: i32| tickets.is_wanted(x, z);
+
+    let removed = work_queue.retain(|job| in_range(job.x, job.z));
+    let loads_dropped = removed[ChunkJobKind::Load as usize];
+    let generations_dropped = removed[ChunkJobKind::Generate as usize];
+
+    let meshes_cancelled = mesh_tasks.cancel_outside(in_range) + removed[ChunkJobKind::Mesh as usize];
+
+    if loads_dropped + generations_dropped + meshes_cancelled == 0 {
+        return;
+    }
+    stats.loads_dropped += loads_dropped;
+    stats.generations_dropped += generations_dropped;
+    stats.meshes_cancelled += meshes_cancelled;
+    info!(
+        "Hors de portée : {} chargements, {} générations et {} maillages annulés (total : {:?})",
+        loads_dropped, generations_dropped, meshes_cancelled, *stats,
+    );
+}
 
 pub fn update_visible_sessions(
     camera_query: Query<(&GlobalTransform, &Frustum), With<Camera3d>>,
@@ -136,3 +446,27 @@ pub fn update_visible_sessions(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_radius_adjusts_and_meshing_stays_within_far_lod() {
+        let mut view_distance = ViewDistance { simulation: 2, meshing: 8, far_lod: 12 };
+
+        view_distance.adjust(ViewRadius::Simulation, 1);
+        assert_eq!(view_distance, ViewDistance { simulation: 3, meshing: 8, far_lod: 12 });
+
+        view_distance.adjust(ViewRadius::Meshing, 6);
+        assert_eq!(view_distance, ViewDistance { simulation: 3, meshing: 14, far_lod: 14 });
+
+        view_distance.adjust(ViewRadius::FarLod, -4);
+        assert_eq!(view_distance, ViewDistance { simulation: 3, meshing: 10, far_lod: 10 });
+
+        view_distance.adjust(ViewRadius::Simulation, -10);
+        view_distance.adjust(ViewRadius::FarLod, 100);
+        assert_eq!(view_distance, ViewDistance { simulation: 0, meshing: 10, far_lod: MAX_VIEW_DISTANCE });
+        assert_eq!(view_distance.load_radius(), MAX_VIEW_DISTANCE);
+    }
+}
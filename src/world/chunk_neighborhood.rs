@@ -0,0 +1,121 @@
+use std::sync::Arc;
+use crate::constants::CHUNK_SIZE;
+use crate::generation::biome::BiomeType;
+use crate::generation::generate_biome_map::BiomeMap;
+use crate::world::block::BlockType;
+use crate::world::chunk::Chunk;
+use crate::world::light::MAX_LIGHT;
+use crate::world::load_save_chunk::WorldData;
+use crate::world::world_config::WorldConfig;
+
+/// Vue en lecture seule d'un chunk et de ses 8 voisins, prise à un instant donné.
+/// Les chunks sont partagés par `Arc` : la construire ne copie aucun bloc, et elle peut
+/// être envoyée dans une tâche asynchrone sans cloner tout `WorldData`.
+///
+/// Toutes les requêtes se font en coordonnées locales au chunk central :
+/// x et z valent de -16 à 31, les valeurs hors de 0..16 tombent chez les voisins.
+#[derive(Clone)]
+pub struct ChunkNeighborhood {
+    pub x: i32,
+    pub z: i32,
+    chunks: [Option<Arc<Chunk>>; 9],
+    biome_map: Option<Arc<BiomeMap>>,
+    config: Arc<WorldConfig>,
+    /// Côté, en blocs, des cases maillées : 1 en pleine résolution, 2 à 8 pour les niveaux de détail
+    scale: usize,
+}
+
+impl ChunkNeighborhood {
+    /// Retourne None si le chunk central n'est pas chargé
+    pub fn new(world: &WorldData, x: i32, z: i32, biome_map: Option<Arc<BiomeMap>>) -> Option<Self> {
+        if !world.chunks_loaded.contains_key(&(x, z)) {
+            return None;
+        }
+
+        let chunks = std::array::from_fn(|i| {
+            let dx = (i % 3) as i32 - 1;
+            let dz = (i / 3) as i32 - 1;
+            world.chunks_loaded.get(&(x + dx, z + dz)).cloned()
+        });
+
+        Some(Self { x, z, chunks, biome_map, config: world.config.clone(), scale: 1 })
+    }
+
+    /// Même position et même carte des biomes, maillé par cases de `scale` blocs. Chaque chunk présent
+    /// est remplacé par `f(dx, dz, chunk)`, ou retiré si `f` retourne None (le chunk central doit rester).
+    pub fn rescaled(&self, scale: usize, mut f: impl FnMut(i32, i32, &Chunk) -> Option<Chunk>) -> Self {
+        let chunks = std::array::from_fn(|i| {
+            let (dx, dz) = ((i % 3) as i32 - 1, (i / 3) as i32 - 1);
+            let chunk = self.chunks[i].as_deref()?;
+            f(dx, dz, chunk).map(Arc::new)
+        });
+        Self { x: self.x, z: self.z, chunks, biome_map: self.biome_map.clone(), config: self.config.clone(), scale }
+    }
+
+    pub fn scale(&self) -> usize {
+        self.scale
+    }
+
+    pub fn center(&self) -> &Chunk {
+        self.chunks[4].as_ref().expect("Le chunk central est toujours présent")
+    }
+
+    /// Le voisin en (dx, dz), avec dx et dz dans -1..=1
+    pub fn neighbor(&self, dx: i32, dz: i32) -> Option<&Chunk> {
+        self.chunks[((dz + 1) * 3 + dx + 1) as usize].as_deref()
+    }
+
+    fn locate(&self, x: i32, z: i32) -> Option<(&Chunk, usize, usize)> {
+        let size = CHUNK_SIZE as i32;
+        let dx = x.div_euclid(size);
+        let dz = z.div_euclid(size);
+        if dx.abs() > 1 || dz.abs() > 1 {
+            return None;
+        }
+        let chunk = self.neighbor(dx, dz)?;
+        Some((chunk, x.rem_euclid(size) as usize, z.rem_euclid(size) as usize))
+    }
+
+    pub fn get_block(&self, x: i32, y: i32, z: i32) -> BlockType {
+        if !self.config.contains_y(y as isize) {
+            return BlockType::AIR;
+        }
+        match self.locate(x, z) {
+            Some((chunk, lx, lz)) => chunk.get_block_at(lx, y as isize, lz),
+            None => BlockType::AIR,
+        }
+    }
+
+    /// Lumière du ciel ; plein jour au-dessus du monde ou chez un voisin absent
+    pub fn get_sky_light(&self, x: i32, y: i32, z: i32) -> u8 {
+        let config = &self.config;
+        if y < config.min_build_height {
+            return 0;
+        }
+        if y >= config.max_build_height {
+            return MAX_LIGHT;
+        }
+        match self.locate(x, z) {
+            Some((chunk, lx, lz)) => chunk.get_sky_light_at(lx, y as isize, lz),
+            None => MAX_LIGHT,
+        }
+    }
+
+    pub fn get_block_light(&self, x: i32, y: i32, z: i32) -> u8 {
+        if !self.config.contains_y(y as isize) {
+            return 0;
+        }
+        match self.locate(x, z) {
+            Some((chunk, lx, lz)) => chunk.get_block_light_at(lx, y as isize, lz),
+            None => 0,
+        }
+    }
+
+    /// Biome à la colonne locale (x, z) ; None si la carte des biomes n'est pas encore prête
+    pub fn get_biome(&self, x: i32, z: i32) -> Option<BiomeType> {
+        let biome_map = self.biome_map.as_ref()?;
+        let world_x = self.x as i64 * CHUNK_SIZE as i64 + x as i64;
+        let world_z = self.z as i64 * CHUNK_SIZE as i64 + z as i64;
+        Some(biome_map.get_biome(world_x, world_z))
+    }
+}
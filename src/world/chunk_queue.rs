@@ -0,0 +1,263 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashSet};
+use bevy::math::Affine3A;
+use bevy::prelude::*;
+use bevy::render::primitives::{Aabb, Frustum};
+use bevy::render::view::VisibilitySystems;
+use crate::constants::CHUNK_SIZE;
+use crate::world::chunk_loadings_logic::PlayerChunk;
+use crate::world::world_config::WorldConfig;
+
+/// Pénalité (en chunks de distance) des chunks hors du champ de la caméra :
+/// un chunk visible à 6 chunks passe avant un chunk dans le dos à 3 chunks
+const OFFSCREEN_PENALTY: f32 = 4.0;
+
+/// Rotation de la caméra (en degrés) au-delà de laquelle les priorités sont recalculées
+const REPRIORITIZE_ANGLE: f32 = 15.0;
+
+/// Étape du travail en attente pour un chunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChunkJobKind {
+    Load,
+    Generate,
+    Mesh,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ChunkJob {
+    pub x: i32,
+    pub z: i32,
+    pub kind: ChunkJobKind,
+    /// Plus petit = plus urgent
+    priority: f32,
+}
+
+/// Travail rangé dans le tas : le plus urgent (plus petite priorité) est le plus grand.
+/// À priorité égale, l'étape la plus en amont (chargement, puis génération, puis maillage) passe d'abord.
+struct Urgent(ChunkJob);
+
+impl PartialEq for Urgent {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Urgent {}
+
+impl PartialOrd for Urgent {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Urgent {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.priority.total_cmp(&self.0.priority)
+            .then_with(|| (other.0.kind as usize).cmp(&(self.0.kind as usize)))
+    }
+}
+
+/// File unique du travail en attente (chargement, génération, maillage), ordonnée par distance
+/// au joueur et par présence dans le champ de la caméra, l'étape ne départageant que les égalités.
+/// Chaque étape prend ses travaux les plus urgents, dans la limite de ses tâches en parallèle.
+#[derive(Resource, Default)]
+pub struct ChunkWorkQueue {
+    heap: BinaryHeap<Urgent>,
+    /// Nombre de travaux en attente par étape, indicé par `ChunkJobKind as usize`
+    counts: [usize; 3],
+    pending: HashSet<(i32, i32, ChunkJobKind)>,
+    /// Point de vue utilisé pour les priorités des nouveaux travaux
+    viewpoint: Option<Viewpoint>,
+}
+
+#[derive(Clone)]
+struct Viewpoint {
+    player_chunk: IVec2,
+    frustum: Option<Frustum>,
+    /// Direction de la caméra lors du calcul des priorités
+    forward: Option<Vec3>,
+    min_y: f32,
+    max_y: f32,
+}
+
+impl Viewpoint {
+    fn priority(&self, x: i32, z: i32) -> f32 {
+        let distance = IVec2::new(x, z).as_vec2().distance(self.player_chunk.as_vec2());
+        let size = CHUNK_SIZE as f32;
+        let aabb = Aabb::from_min_max(
+            Vec3::new(x as f32 * size, self.min_y, z as f32 * size),
+            Vec3::new((x + 1) as f32 * size, self.max_y, (z + 1) as f32 * size),
+        );
+        let visible = self.frustum.as_ref()
+            .is_none_or(|frustum| frustum.intersects_obb(&aabb, &Affine3A::IDENTITY, true, false));
+        if visible { distance } else { distance + OFFSCREEN_PENALTY }
+    }
+}
+
+impl ChunkWorkQueue {
+    /// Ajoute un travail, sauf s'il est déjà en attente
+    pub fn push(&mut self, x: i32, z: i32, kind: ChunkJobKind) {
+        if !self.pending.insert((x, z, kind)) {
+            return;
+        }
+        let priority = self.viewpoint.as_ref().map_or(0.0, |viewpoint| viewpoint.priority(x, z));
+        self.heap.push(Urgent(ChunkJob { x, z, kind, priority }));
+        self.counts[kind as usize] += 1;
+    }
+
+    /// Retire et retourne le travail le plus urgent de cette étape. Les travaux plus urgents
+    /// des autres étapes, sortis du tas pour l'atteindre, y sont remis.
+    pub fn pop(&mut self, kind: ChunkJobKind) -> Option<ChunkJob> {
+        if self.counts[kind as usize] == 0 {
+            return None;
+        }
+        let mut skipped = Vec::new();
+        let job = loop {
+            let Urgent(job) = self.heap.pop()?;
+            if job.kind == kind {
+                break job;
+            }
+            skipped.push(Urgent(job));
+        };
+        self.heap.extend(skipped);
+
+        self.counts[kind as usize] -= 1;
+        self.pending.remove(&(job.x, job.z, job.kind));
+        Some(job)
+    }
+
+    pub fn contains(&self, x: i32, z: i32, kind: ChunkJobKind) -> bool {
+        self.pending.contains(&(x, z, kind))
+    }
+
+    pub fn len(&self, kind: ChunkJobKind) -> usize {
+        self.counts[kind as usize]
+    }
+
+    /// Garde les travaux pour lesquels `keep` est vrai ; retourne le nombre de travaux retirés par étape
+    pub fn retain(&mut self, mut keep: impl FnMut(&ChunkJob) -> bool) -> [usize; 3] {
+        let mut removed = [0; 3];
+        let pending = &mut self.pending;
+        self.heap.retain(|Urgent(job)| {
+            if keep(job) {
+                return true;
+            }
+            pending.remove(&(job.x, job.z, job.kind));
+            removed[job.kind as usize] += 1;
+            false
+        });
+        for (count, removed) in self.counts.iter_mut().zip(removed) {
+            *count -= removed;
+        }
+        removed
+    }
+
+    fn reprioritize(&mut self, viewpoint: Viewpoint) {
+        let mut jobs = std::mem::take(&mut self.heap).into_vec();
+        for Urgent(job) in jobs.iter_mut() {
+            job.priority = viewpoint.priority(job.x, job.z);
+        }
+        self.heap = BinaryHeap::from(jobs);
+        self.viewpoint = Some(viewpoint);
+    }
+
+    /// Faut-il recalculer les priorités pour ce point de vue ?
+    fn is_outdated(&self, player_chunk: IVec2, forward: Option<Vec3>) -> bool {
+        let Some(viewpoint) = &self.viewpoint else {
+            return true;
+        };
+        if viewpoint.player_chunk != player_chunk {
+            return true;
+        }
+        match (viewpoint.forward, forward) {
+            (Some(previous), Some(forward)) => previous.angle_between(forward) > REPRIORITIZE_ANGLE.to_radians(),
+            (previous, forward) => previous.is_some() != forward.is_some(),
+        }
+    }
+}
+
+pub struct ChunkQueuePlugin;
+
+impl Plugin for ChunkQueuePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ChunkWorkQueue>();
+        app.add_systems(PostUpdate, prioritize_chunk_work.after(VisibilitySystems::UpdateFrusta));
+    }
+}
+
+/// Recalcule les priorités quand le joueur change de chunk ou que la caméra tourne assez
+fn prioritize_chunk_work(
+    player_chunk: Res<PlayerChunk>,
+    cameras: Query<(&Frustum, &GlobalTransform), With<Camera3d>>,
+    mut queue: ResMut<ChunkWorkQueue>,
+    config: Res<WorldConfig>,
+) {
+    let camera = cameras.iter().next();
+    let forward = camera.map(|(_, transform)| *transform.forward());
+    if !queue.is_outdated(player_chunk.current_chunk, forward) {
+        return;
+    }
+
+    queue.reprioritize(Viewpoint {
+        player_chunk: player_chunk.current_chunk,
+        frustum: camera.map(|(frustum, _)| frustum.clone()),
+        forward,
+        min_y: config.min_build_height as f32,
+        max_y: config.max_build_height as f32,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue_around_origin() -> ChunkWorkQueue {
+        let mut queue = ChunkWorkQueue::default();
+        queue.reprioritize(Viewpoint { player_chunk: IVec2::ZERO, frustum: None, forward: None, min_y: 0.0, max_y: 384.0 });
+        queue
+    }
+
+    #[test]
+    fn each_kind_pops_its_nearest_job() {
+        let mut queue = queue_around_origin();
+        queue.push(3, 0, ChunkJobKind::Mesh);
+        queue.push(1, 0, ChunkJobKind::Load);
+        queue.push(0, 2, ChunkJobKind::Mesh);
+        queue.push(1, 0, ChunkJobKind::Mesh);
+        queue.push(1, 0, ChunkJobKind::Mesh);
+        assert_eq!(queue.len(ChunkJobKind::Mesh), 3);
+
+        let pop = |queue: &mut ChunkWorkQueue, kind| queue.pop(kind).map(|job| (job.x, job.z));
+        assert_eq!(pop(&mut queue, ChunkJobKind::Mesh), Some((1, 0)));
+        assert_eq!(pop(&mut queue, ChunkJobKind::Mesh), Some((0, 2)));
+        assert_eq!(pop(&mut queue, ChunkJobKind::Generate), None);
+        // Le chargement sorti du tas pour atteindre les maillages y est toujours
+        assert_eq!(pop(&mut queue, ChunkJobKind::Load), Some((1, 0)));
+        assert_eq!(queue.len(ChunkJobKind::Mesh), 1);
+        assert!(queue.contains(3, 0, ChunkJobKind::Mesh));
+    }
+
+    #[test]
+    fn kind_only_breaks_ties_in_the_shared_order() {
+        let mut queue = queue_around_origin();
+        queue.push(2, 0, ChunkJobKind::Load);
+        queue.push(1, 0, ChunkJobKind::Mesh);
+        queue.push(0, 1, ChunkJobKind::Load);
+
+        let order: Vec<_> = std::iter::from_fn(|| queue.heap.pop().map(|Urgent(job)| (job.x, job.z, job.kind))).collect();
+        assert_eq!(order, [(0, 1, ChunkJobKind::Load), (1, 0, ChunkJobKind::Mesh), (2, 0, ChunkJobKind::Load)]);
+    }
+
+    #[test]
+    fn retain_keeps_counts_per_kind() {
+        let mut queue = queue_around_origin();
+        queue.push(0, 0, ChunkJobKind::Load);
+        queue.push(5, 0, ChunkJobKind::Load);
+        queue.push(5, 0, ChunkJobKind::Mesh);
+
+        let removed = queue.retain(|job| job.x < 5);
+        assert_eq!(removed, [1, 0, 1]);
+        assert_eq!((queue.len(ChunkJobKind::Load), queue.len(ChunkJobKind::Mesh)), (1, 0));
+        assert_eq!(queue.pop(ChunkJobKind::Mesh).map(|job| job.x), None);
+    }
+}
@@ -0,0 +1,172 @@
+use std::collections::{HashMap, HashSet};
+use bevy::app::{App, Plugin, PostUpdate};
+use bevy::log::warn;
+use bevy::prelude::{Event, EventWriter, ResMut, Resource};
+
+/// Étape du cycle de vie d'un chunk.
+///
+/// Requested → Loading → (Generating →) Lit → Meshing → Ready, puis Ready ↔ Meshing à chaque
+/// remaillage. Depuis n'importe quelle étape, le chunk peut passer à Unloading, puis à Saving
+/// s'il a été modifié, avant d'être oublié. Redemandé pendant sa sauvegarde, il repasse à Requested.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChunkStatus {
+    /// Voulu par un chargeur, en attente dans la file de chargement
+    Requested,
+    /// Lecture sur disque en cours
+    Loading,
+    /// Absent du disque, génération en attente ou en cours
+    Generating,
+    /// Blocs et lumière en place dans `WorldData`, pas encore maillé
+    Lit,
+    /// Maillage en attente ou en cours
+    Meshing,
+    /// Maillé et affiché
+    Ready,
+    /// Plus voulu par aucun chargeur, ses entités sont en cours de suppression
+    Unloading,
+    /// Modifié depuis son chargement, écriture sur disque avant de l'oublier
+    Saving,
+}
+
+impl ChunkStatus {
+    pub fn can_transition_to(self, next: ChunkStatus) -> bool {
+        use ChunkStatus::*;
+        match (self, next) {
+            (Requested, Loading)
+            | (Loading, Generating | Lit)
+            | (Generating, Lit)
+            | (Lit | Ready, Meshing)
+            | (Meshing, Ready)
+            | (Unloading, Saving)
+            | (Saving, Requested) => true,
+            (Unloading | Saving, _) => false,
+            (_, Unloading) => true,
+            _ => false,
+        }
+    }
+
+    /// Les blocs du chunk sont dans `WorldData::chunks_loaded`
+    pub fn is_loaded(self) -> bool {
+        matches!(self, ChunkStatus::Lit | ChunkStatus::Meshing | ChunkStatus::Ready)
+    }
+}
+
+/// Envoyé à chaque changement d'étape d'un chunk. `from` vaut None quand le chunk vient d'être demandé,
+/// `to` vaut None quand il est oublié.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkStatusChangedEvent {
+    pub x: i32,
+    pub z: i32,
+    pub from: Option<ChunkStatus>,
+    pub to: Option<ChunkStatus>,
+}
+
+/// Étape de chaque chunk connu. Seuls les systèmes de chargement, de génération, de maillage
+/// et de déchargement la font avancer ; les autres s'en servent pour savoir où en est un chunk.
+#[derive(Resource, Default)]
+pub struct ChunkStates {
+    statuses: HashMap<(i32, i32), ChunkStatus>,
+    /// Chunks dont un bloc a changé depuis leur chargement
+    modified: HashSet<(i32, i32)>,
+    /// Changements pas encore envoyés en événements
+    changes: Vec<ChunkStatusChangedEvent>,
+}
+
+impl ChunkStates {
+    pub fn status(&self, x: i32, z: i32) -> Option<ChunkStatus> {
+        self.statuses.get(&(x, z)).copied()
+    }
+
+    pub fn is(&self, x: i32, z: i32, status: ChunkStatus) -> bool {
+        self.status(x, z) == Some(status)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = ((i32, i32), ChunkStatus)> + '_ {
+        self.statuses.iter().map(|(&pos, &status)| (pos, status))
+    }
+
+    pub fn with_status(&self, status: ChunkStatus) -> impl Iterator<Item = (i32, i32)> + '_ {
+        self.iter().filter(move |&(_, s)| s == status).map(|(pos, _)| pos)
+    }
+
+    pub fn count(&self, status: ChunkStatus) -> usize {
+        self.statuses.values().filter(|&&s| s == status).count()
+    }
+
+    pub fn is_modified(&self, x: i32, z: i32) -> bool {
+        self.modified.contains(&(x, z))
+    }
+
+    /// Le chunk devra être sauvegardé à son déchargement
+    pub fn mark_modified(&mut self, x: i32, z: i32) {
+        if self.status(x, z).is_some_and(ChunkStatus::is_loaded) {
+            self.modified.insert((x, z));
+        }
+    }
+
+    /// Premier ticket d'un chunk inconnu : il passe à Requested
+    pub fn request(&mut self, x: i32, z: i32) -> bool {
+        if let Some(current) = self.status(x, z) {
+            warn!("Chunk ({}, {}) demandé alors qu'il est déjà {:?}", x, z, current);
+            return false;
+        }
+        self.statuses.insert((x, z), ChunkStatus::Requested);
+        self.changes.push(ChunkStatusChangedEvent { x, z, from: None, to: Some(ChunkStatus::Requested) });
+        true
+    }
+
+    /// Fait passer le chunk à l'étape `next`. Une transition invalide est refusée (avec un avertissement) ;
+    /// rester à la même étape ne fait rien.
+    pub fn set(&mut self, x: i32, z: i32, next: ChunkStatus) -> bool {
+        let Some(current) = self.status(x, z) else {
+            warn!("Chunk ({}, {}) inconnu, passage à {:?} refusé", x, z, next);
+            return false;
+        };
+        if current == next {
+            return true;
+        }
+        if !current.can_transition_to(next) {
+            warn!("Chunk ({}, {}) : transition {:?} → {:?} refusée", x, z, current, next);
+            return false;
+        }
+        self.statuses.insert((x, z), next);
+        self.changes.push(ChunkStatusChangedEvent { x, z, from: Some(current), to: Some(next) });
+        true
+    }
+
+    /// Oublie un chunk déchargé (depuis Unloading ou Saving)
+    pub fn remove(&mut self, x: i32, z: i32) -> bool {
+        let Some(current) = self.status(x, z) else {
+            return false;
+        };
+        if !matches!(current, ChunkStatus::Unloading | ChunkStatus::Saving) {
+            warn!("Chunk ({}, {}) oublié alors qu'il est {:?}", x, z, current);
+            return false;
+        }
+        self.statuses.remove(&(x, z));
+        self.modified.remove(&(x, z));
+        self.changes.push(ChunkStatusChangedEvent { x, z, from: Some(current), to: None });
+        true
+    }
+}
+
+pub struct ChunkStatePlugin;
+
+impl Plugin for ChunkStatePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ChunkStates>();
+        app.add_event::<ChunkStatusChangedEvent>();
+        app.add_systems(PostUpdate, send_status_events);
+    }
+}
+
+/// Envoie, dans l'ordre, les changements d'étape de la frame
+fn send_status_events(
+    mut states: ResMut<ChunkStates>,
+    mut writer: EventWriter<ChunkStatusChangedEvent>,
+) {
+    if states.changes.is_empty() {
+        return;
+    }
+    writer.write_batch(std::mem::take(&mut states.changes));
+}
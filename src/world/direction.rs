@@ -0,0 +1,14 @@
+/// Direction d'une face de bloc
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    Down,
+    North,
+    South,
+    East,
+    West,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 6] = [Direction::Up, Direction::Down, Direction::North, Direction::South, Direction::East, Direction::West];
+}
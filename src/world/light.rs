@@ -0,0 +1,253 @@
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+use crate::constants::{CHUNK_SIZE, SECTION_HEIGHT};
+use crate::world::block::{BlockRegistry, BlockType};
+use crate::world::chunk::Chunk;
+use crate::world::load_save_chunk::WorldData;
+
+pub const MAX_LIGHT: u8 = 15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LightKind {
+    Sky,
+    Block,
+}
+
+const NEIGHBORS: [(isize, isize, isize); 6] = [
+    (0, 1, 0),
+    (0, -1, 0),
+    (0, 0, -1),
+    (0, 0, 1),
+    (1, 0, 0),
+    (-1, 0, 0),
+];
+
+/// Accès à la lumière et aux blocs pour la propagation.
+/// `None` signifie que la position est hors des données disponibles (chunk non chargé).
+pub trait LightStorage {
+    fn light(&self, kind: LightKind, x: isize, y: isize, z: isize) -> Option<u8>;
+    fn set_light(&mut self, kind: LightKind, x: isize, y: isize, z: isize, value: u8);
+    fn block(&self, x: isize, y: isize, z: isize) -> BlockType;
+}
+
+/// Lumière reçue par un voisin depuis une case de niveau `level`.
+/// La lumière du ciel descend sans perte à travers les blocs qui ne l'atténuent pas.
+fn propagated_level(kind: LightKind, level: u8, dy: isize, opacity: u8) -> u8 {
+    if kind == LightKind::Sky && dy == -1 && level == MAX_LIGHT && opacity == 0 {
+        MAX_LIGHT
+    } else {
+        level.saturating_sub(opacity.max(1))
+    }
+}
+
+/// Propage la lumière depuis toutes les positions de la file (remplissage en largeur)
+pub fn propagate_light<S: LightStorage>(storage: &mut S, kind: LightKind, queue: &mut VecDeque<(isize, isize, isize)>) {
+    let registry = BlockRegistry::global();
+
+    while let Some((x, y, z)) = queue.pop_front() {
+        let Some(level) = storage.light(kind, x, y, z) else { continue };
+        if level <= 1 {
+            continue;
+        }
+
+        for (dx, dy, dz) in NEIGHBORS {
+            let (nx, ny, nz) = (x + dx, y + dy, z + dz);
+            let Some(current) = storage.light(kind, nx, ny, nz) else { continue };
+
+            let opacity = registry.get(storage.block(nx, ny, nz)).opacity;
+            if opacity >= MAX_LIGHT {
+                continue;
+            }
+
+            let new_level = propagated_level(kind, level, dy, opacity);
+            if new_level > current {
+                storage.set_light(kind, nx, ny, nz, new_level);
+                queue.push_back((nx, ny, nz));
+            }
+        }
+    }
+}
+
+/// Éteint la lumière issue des positions de la file, puis relance la propagation
+/// depuis les sources encore présentes en bordure de la zone éteinte.
+pub fn remove_light<S: LightStorage>(storage: &mut S, kind: LightKind, queue: &mut VecDeque<(isize, isize, isize, u8)>) {
+    let mut relight = VecDeque::new();
+
+    while let Some((x, y, z, level)) = queue.pop_front() {
+        for (dx, dy, dz) in NEIGHBORS {
+            let (nx, ny, nz) = (x + dx, y + dy, z + dz);
+            let Some(neighbor_level) = storage.light(kind, nx, ny, nz) else { continue };
+            if neighbor_level == 0 {
+                continue;
+            }
+
+            let fed_by_sky_column = kind == LightKind::Sky && dy == -1 && level == MAX_LIGHT && neighbor_level == MAX_LIGHT;
+            if neighbor_level < level || fed_by_sky_column {
+                storage.set_light(kind, nx, ny, nz, 0);
+                queue.push_back((nx, ny, nz, neighbor_level));
+
+                // Une source éteinte au passage se rallume aussitôt
+                let emission = storage.block(nx, ny, nz).definition().light_emission;
+                if kind == LightKind::Block && emission > 0 {
+                    storage.set_light(kind, nx, ny, nz, emission);
+                    relight.push_back((nx, ny, nz));
+                }
+            } else {
+                relight.push_back((nx, ny, nz));
+            }
+        }
+    }
+
+    propagate_light(storage, kind, &mut relight);
+}
+
+/// Calcule la lumière d'un chunk fraîchement généré, sans tenir compte de ses voisins.
+/// Les bordures sont raccordées ensuite par `WorldData::stitch_light_borders`.
+/// Le ciel éclaire depuis le haut de la plus haute section du chunk.
+pub fn light_chunk(chunk: &mut Chunk) {
+    let height = SECTION_HEIGHT as isize;
+    let (Some(bottom), Some(top)) = (
+        chunk.sections.iter().map(|s| s.y as isize * height).min(),
+        chunk.sections.iter().map(|s| (s.y as isize + 1) * height - 1).max(),
+    ) else {
+        return;
+    };
+
+    let mut sky_queue = VecDeque::new();
+    for x in 0..CHUNK_SIZE as isize {
+        for z in 0..CHUNK_SIZE as isize {
+            let opacity = chunk.block(x, top, z).definition().opacity;
+            chunk.set_light(LightKind::Sky, x, top, z, MAX_LIGHT.saturating_sub(opacity));
+            sky_queue.push_back((x, top, z));
+        }
+    }
+    propagate_light(chunk, LightKind::Sky, &mut sky_queue);
+
+    let mut block_queue = VecDeque::new();
+    for y in bottom..=top {
+        for x in 0..CHUNK_SIZE as isize {
+            for z in 0..CHUNK_SIZE as isize {
+                let emission = chunk.block(x, y, z).definition().light_emission;
+                if emission > 0 {
+                    chunk.set_light(LightKind::Block, x, y, z, emission);
+                    block_queue.push_back((x, y, z));
+                }
+            }
+        }
+    }
+    propagate_light(chunk, LightKind::Block, &mut block_queue);
+}
+
+/// Met à jour la lumière autour d'un bloc qui vient de changer (coordonnées mondiales).
+/// Ajoute à `sections` chaque section (x, z, section_y) dont le maillage lit une case dont
+/// la lumière a changé : celle de la case et celles de ses 6 voisines, dont les faces sont éclairées par elle.
+pub fn relight_block(world: &mut WorldData, x: isize, y: isize, z: isize, sections: &mut HashSet<(i32, i32, i8)>) {
+    let definition = world.get_block_at(x, y, z).definition();
+    let top = world.config.max_build_height as isize - 1;
+    let world = &mut TrackedLight { world, sections };
+
+    for kind in [LightKind::Sky, LightKind::Block] {
+        let Some(old_level) = world.light(kind, x, y, z) else { continue };
+
+        world.set_light(kind, x, y, z, 0);
+        let mut removal = VecDeque::from([(x, y, z, old_level)]);
+        remove_light(world, kind, &mut removal);
+
+        // Le nouveau bloc peut lui-même émettre, ou laisser passer la lumière des voisins
+        let mut sources = VecDeque::new();
+        if kind == LightKind::Block && definition.light_emission > 0 {
+            world.set_light(kind, x, y, z, definition.light_emission);
+            sources.push_back((x, y, z));
+        }
+        if kind == LightKind::Sky && y == top {
+            world.set_light(kind, x, y, z, MAX_LIGHT.saturating_sub(definition.opacity));
+            sources.push_back((x, y, z));
+        }
+        for (dx, dy, dz) in NEIGHBORS {
+            sources.push_back((x + dx, y + dy, z + dz));
+        }
+        propagate_light(world, kind, &mut sources);
+    }
+}
+
+/// `WorldData` qui note les sections touchées par chaque changement de lumière
+struct TrackedLight<'a> {
+    world: &'a mut WorldData,
+    sections: &'a mut HashSet<(i32, i32, i8)>,
+}
+
+impl LightStorage for TrackedLight<'_> {
+    fn light(&self, kind: LightKind, x: isize, y: isize, z: isize) -> Option<u8> {
+        self.world.light(kind, x, y, z)
+    }
+
+    fn set_light(&mut self, kind: LightKind, x: isize, y: isize, z: isize, value: u8) {
+        if self.world.light(kind, x, y, z) == Some(value) {
+            return;
+        }
+        self.world.set_light(kind, x, y, z, value);
+        let (size, height) = (CHUNK_SIZE as isize, SECTION_HEIGHT as isize);
+        for (dx, dy, dz) in std::iter::once((0, 0, 0)).chain(NEIGHBORS) {
+            let (nx, ny, nz) = (x + dx, y + dy, z + dz);
+            self.sections.insert((nx.div_euclid(size) as i32, nz.div_euclid(size) as i32, ny.div_euclid(height) as i8));
+        }
+    }
+
+    fn block(&self, x: isize, y: isize, z: isize) -> BlockType {
+        self.world.block(x, y, z)
+    }
+}
+
+/// Seules les sections présentes du chunk portent de la lumière
+impl LightStorage for Chunk {
+    fn light(&self, kind: LightKind, x: isize, y: isize, z: isize) -> Option<u8> {
+        if x < 0 || z < 0 || x >= CHUNK_SIZE as isize || z >= CHUNK_SIZE as isize {
+            return None;
+        }
+        let section = self.section(y.div_euclid(SECTION_HEIGHT as isize) as i8)?;
+        let (x, y, z) = (x as usize, y.rem_euclid(SECTION_HEIGHT as isize) as usize, z as usize);
+        Some(match kind {
+            LightKind::Sky => section.get_sky_light(x, y, z),
+            LightKind::Block => section.get_block_light(x, y, z),
+        })
+    }
+
+    fn set_light(&mut self, kind: LightKind, x: isize, y: isize, z: isize, value: u8) {
+        let section_y = y.div_euclid(SECTION_HEIGHT as isize) as i8;
+        let local_y = y.rem_euclid(SECTION_HEIGHT as isize) as usize;
+        if let Some(section) = self.section_mut(section_y) {
+            match kind {
+                LightKind::Sky => section.set_sky_light(x as usize, local_y, z as usize, value),
+                LightKind::Block => section.set_block_light(x as usize, local_y, z as usize, value),
+            }
+        }
+    }
+
+    fn block(&self, x: isize, y: isize, z: isize) -> BlockType {
+        self.get_block_at(x as usize, y, z as usize)
+    }
+}
+
+impl LightStorage for WorldData {
+    fn light(&self, kind: LightKind, x: isize, y: isize, z: isize) -> Option<u8> {
+        if !self.config.contains_y(y) {
+            return None;
+        }
+        let chunk_x = x.div_euclid(CHUNK_SIZE as isize) as i32;
+        let chunk_z = z.div_euclid(CHUNK_SIZE as isize) as i32;
+        let chunk = self.chunks_loaded.get(&(chunk_x, chunk_z))?;
+        chunk.light(kind, x.rem_euclid(CHUNK_SIZE as isize), y, z.rem_euclid(CHUNK_SIZE as isize))
+    }
+
+    fn set_light(&mut self, kind: LightKind, x: isize, y: isize, z: isize, value: u8) {
+        let chunk_x = x.div_euclid(CHUNK_SIZE as isize) as i32;
+        let chunk_z = z.div_euclid(CHUNK_SIZE as isize) as i32;
+        if let Some(chunk) = self.chunks_loaded.get_mut(&(chunk_x, chunk_z)) {
+            Arc::make_mut(chunk).set_light(kind, x.rem_euclid(CHUNK_SIZE as isize), y, z.rem_euclid(CHUNK_SIZE as isize), value);
+        }
+    }
+
+    fn block(&self, x: isize, y: isize, z: isize) -> BlockType {
+        self.get_block_at(x, y, z)
+    }
+}
@@ -1,38 +1,88 @@
-use std::collections::{HashMap, VecDeque};
-use std::fs::{File, create_dir_all};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::{File, create_dir_all, rename};
 use std::io::{Read, Write};
 use std::path::Path;
+use std::sync::{Arc, Mutex};
 use bevy::app::{App, Plugin, Update};
 use bevy::log::{error, info};
-use bevy::prelude::{Entity, Event, EventReader, EventWriter, ResMut, Resource};
-use bevy::tasks::{AsyncComputeTaskPool, Task};
+use bevy::prelude::{Entity, Event, EventReader, EventWriter, FromWorld, Res, ResMut, Resource, World};
+use bevy::tasks::{AsyncComputeTaskPool, IoTaskPool, Task};
 use mca::{RegionReader, RegionWriter, RawChunk};
 use fastnbt::{to_writer, from_bytes, SerOpts};
-use fastnbt::Value;
+use fastnbt::{ByteArray, Value};
 use flate2::Status;
 use futures::FutureExt;
 use noise::{NoiseFn, Perlin};
-use crate::constants::{CHUNK_SIZE, SECTION_HEIGHT, WORLD_HEIGHT};
+use crate::constants::{CHUNK_SIZE, SECTION_HEIGHT};
+use crate::world::world_config::WorldConfig;
 use crate::generation::chunk_generation_logic::ToGenerateChunkEvent;
 use crate::world::block::BlockType;
-use crate::world::chunk::Chunk;
+use crate::world::chunk::{Chunk, ChunkSection, LIGHT_ARRAY_SIZE};
+use crate::world::light::{light_chunk, propagate_light, relight_block, LightKind, LightStorage};
 use crate::render::chunk_loadings_mesh_logic::ChunkToUpdateEvent;
+use crate::world::chunk_loadings_logic::ChunkWorkStats;
+use crate::world::chunk_queue::{ChunkJobKind, ChunkWorkQueue};
+use crate::world::chunk_state::{ChunkStates, ChunkStatus};
 use bevy::render::primitives::Aabb;
 
 const MAX_LOAD_TASKS: usize = 5;
 
-#[derive(Resource, Default, Clone)]
+#[derive(Resource, Clone)]
 pub struct WorldData {
-    pub chunks_loaded: HashMap<(i32,i32), Chunk>,
+    pub chunks_loaded: HashMap<(i32,i32), Arc<Chunk>>,
     pub chunks_sections_meshes: HashMap<(i32,i32, i32), Vec<(Entity, Aabb)>>,
+    /// Copie de la ressource `WorldConfig`, partagée avec les tâches asynchrones
+    pub config: Arc<WorldConfig>,
 }
 
+impl WorldData {
+    pub fn new(config: Arc<WorldConfig>) -> Self {
+        Self { chunks_loaded: HashMap::new(), chunks_sections_meshes: HashMap::new(), config }
+    }
+}
+
+impl FromWorld for WorldData {
+    fn from_world(world: &mut World) -> Self {
+        Self::new(Arc::new(world.resource::<WorldConfig>().clone()))
+    }
+}
+
+/// Lectures en cours ; les demandes en attente sont dans `ChunkWorkQueue`
 #[derive(Default, Resource)]
 pub struct ChunkLoadQueue {
-    pub queue: VecDeque<ToLoadChunkEvent>,
     pub current_tasks: Vec<Task<(i32, i32, Chunk)>>,
 }
 
+/// Sauvegardes en cours, avec la copie du chunk qu'elles écrivent
+#[derive(Default, Resource)]
+pub struct ChunkSaves {
+    saving: HashMap<(i32, i32), (Arc<Chunk>, Task<()>)>,
+}
+
+impl ChunkSaves {
+    /// Écrit le chunk sur disque en tâche de fond. Une sauvegarde encore en cours du même chunk
+    /// est attendue avant d'écrire, pour que la plus récente soit la dernière écrite.
+    pub fn save(&mut self, chunk: Arc<Chunk>) {
+        let (x, z) = (chunk.x, chunk.z);
+        let previous = self.saving.remove(&(x, z)).map(|(_, task)| task);
+        let to_write = chunk.clone();
+        let task = IoTaskPool::get().spawn(async move {
+            if let Some(previous) = previous {
+                previous.await;
+            }
+            if let Err(e) = save_chunk(&to_write) {
+                error!("Sauvegarde du chunk ({}, {}) impossible : {}", x, z, e);
+            }
+        });
+        self.saving.insert((x, z), (chunk, task));
+    }
+
+    /// Chunk dont la sauvegarde n'est pas encore écrite : il est plus récent que la version sur disque
+    pub fn pending(&self, x: i32, z: i32) -> Option<Arc<Chunk>> {
+        self.saving.get(&(x, z)).map(|(chunk, _)| chunk.clone())
+    }
+}
+
 pub struct WorldDataPlugin;
 
 #[derive(Default, Event)]
@@ -58,13 +108,15 @@ struct ChunkLoadedEvent {
 impl Plugin for WorldDataPlugin {
     fn build(&self, app: &mut App) {
         app
-            .insert_resource(WorldData::default())
+            .init_resource::<WorldData>()
             .add_event::<ToGenerateChunkEvent>()
             .add_event::<ToUnloadChunkEvent>()
 
             .add_event::<ToLoadChunkEvent>()
             .add_event::<ChunkLoadedEvent>()
             .init_resource::<ChunkLoadQueue>()
+            .init_resource::<ChunkSaves>()
+            .add_systems(Update, collect_finished_saves)
             .add_systems(Update, enqueue_load_requests)
             .add_systems(Update, load_chunks_system)
             .add_systems(Update, collect_load_chunks_system)
@@ -73,29 +125,38 @@ impl Plugin for WorldDataPlugin {
 }
 
 fn enqueue_load_requests(
-    mut queue: ResMut<ChunkLoadQueue>,
+    mut work_queue: ResMut<ChunkWorkQueue>,
     mut event_reader: EventReader<ToLoadChunkEvent>,
 ) {
     for event in event_reader.read() {
-        if !queue.queue.iter().any(|e| e.x == event.x && e.z == event.z) {
-            queue.queue.push_back(event.clone());
-        }
+        work_queue.push(event.x, event.z, ChunkJobKind::Load);
     }
 }
 
 use std::time::{Duration, SystemTime};
 fn load_chunks_system(
     mut queue: ResMut<ChunkLoadQueue>,
+    mut work_queue: ResMut<ChunkWorkQueue>,
+    mut states: ResMut<ChunkStates>,
+    saves: Res<ChunkSaves>,
 ) {
     let task_pool = AsyncComputeTaskPool::get();
 
     while queue.current_tasks.len() < MAX_LOAD_TASKS {
-        if let Some(event) = queue.queue.pop_front() {
-            let x = event.x;
-            let z = event.z;
+        if let Some(job) = work_queue.pop(ChunkJobKind::Load) {
+            let x = job.x;
+            let z = job.z;
+            if !states.set(x, z, ChunkStatus::Loading) {
+                continue;
+            }
 
+            // Rechargé avant la fin de sa sauvegarde : la copie en mémoire est la plus récente
+            let pending = saves.pending(x, z);
             let task = task_pool.spawn(async move {
-                let chunk = load_chunk(x, z).await.expect("Erreur chargement chunk");
+                let chunk = match pending {
+                    Some(chunk) => Chunk::clone(&chunk),
+                    None => load_chunk(x, z).await.expect("Erreur chargement chunk"),
+                };
                 (x, z, chunk)
             });
 
@@ -106,6 +167,19 @@ fn load_chunks_system(
     }
 }
 
+/// Oublie les chunks dont la sauvegarde est écrite, sauf s'ils ont été redemandés entre-temps
+fn collect_finished_saves(mut saves: ResMut<ChunkSaves>, mut states: ResMut<ChunkStates>) {
+    saves.saving.retain(|&(x, z), (_, task)| {
+        if !task.is_finished() {
+            return true;
+        }
+        if states.is(x, z, ChunkStatus::Saving) {
+            states.remove(x, z);
+        }
+        false
+    });
+}
+
 fn collect_load_chunks_system(
     mut queue: ResMut<ChunkLoadQueue>,
     mut writer: EventWriter<ChunkLoadedEvent>,
@@ -125,25 +199,43 @@ fn apply_loaded_chunks(
     mut to_generate: EventWriter<ToGenerateChunkEvent>,
     mut chunk_to_update_event: EventWriter<ChunkToUpdateEvent>,
     mut world_data: ResMut<WorldData>,
+    mut states: ResMut<ChunkStates>,
+    mut stats: ResMut<ChunkWorkStats>,
 ) {
-    let task_pool = AsyncComputeTaskPool::get();
-
     for event in load_events.read() {
         let x = event.x;
         let z = event.z;
 
+        // Chunk déchargé pendant la lecture (ou redemandé depuis, une nouvelle lecture suivra)
+        if !states.is(x, z, ChunkStatus::Loading) {
+            stats.loads_discarded += 1;
+            continue;
+        }
+
         if !event.chunk.sections.is_empty() {
-            world_data.chunks_loaded.insert((x,z), event.chunk.clone());
+            world_data.chunks_loaded.insert((x,z), Arc::new(event.chunk.clone()));
+            world_data.stitch_light_borders(x, z);
+            states.set(x, z, ChunkStatus::Lit);
             chunk_to_update_event.write(ChunkToUpdateEvent { x, z });
+            chunk_to_update_event.write_batch(world_data.loaded_neighbors(x, z).map(|(x, z)| ChunkToUpdateEvent { x, z }));
+        } else {
+            // Jamais sauvegardé : le chunk est généré, puis maillé une fois la génération finie
+            states.set(x, z, ChunkStatus::Generating);
+            to_generate.write(ToGenerateChunkEvent { x, z });
         }
-
-        to_generate.write(ToGenerateChunkEvent { x, z });
     }
 }
 
+/// Fichier de région qui contient le chunk (x, z), en lecture comme en écriture
+fn region_path(x: i32, z: i32) -> String {
+    format!("region/r.{}.{}.mca", x.div_euclid(32), z.div_euclid(32))
+}
+
+/// Une seule écriture de région à la fois : chacune relit le fichier pour garder les autres chunks
+static REGION_WRITE: Mutex<()> = Mutex::new(());
+
 pub async fn load_chunk(x: i32, z: i32) -> anyhow::Result<Chunk> {
-    let (rx, rz) = (x.div_euclid(32), z.div_euclid(32));
-    let region_path = format!("r.{}.{}.mca", rx, rz);
+    let region_path = region_path(x, z);
 
     // Lire le fichier de région s'il existe
     if Path::new(&region_path).exists() {
@@ -170,6 +262,52 @@ pub async fn load_chunk(x: i32, z: i32) -> anyhow::Result<Chunk> {
 
 
 
+/// Écrit le chunk dans son fichier de région. Les autres chunks de la région sont recopiés :
+/// seul l'emplacement de celui-ci change. Le fichier est remplacé d'un coup, une lecture
+/// concurrente voit l'ancienne ou la nouvelle région, jamais une région à moitié écrite.
+pub fn save_chunk(chunk: &Chunk) -> anyhow::Result<()> {
+    let (x, z) = (chunk.x, chunk.z);
+    let mut nbt_buf = Vec::new();
+    to_writer(&mut nbt_buf, &chunk_to_nbt(chunk))?;
+    let compressed = compress(&nbt_buf)?;
+
+    let _guard = REGION_WRITE.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    create_dir_all("region")?;
+    let region_path = region_path(x, z);
+    let slot = ((x & 31) as u8, (z & 31) as u8);
+    let mut writer = RegionWriter::new();
+
+    if Path::new(&region_path).exists() {
+        let mut buf = Vec::new();
+        File::open(&region_path)?.read_to_end(&mut buf)?;
+        let region = RegionReader::new(&buf)?;
+        for other_x in 0..32u8 {
+            for other_z in 0..32u8 {
+                if (other_x, other_z) == slot {
+                    continue;
+                }
+                if let Some(raw) = region.get_chunk(other_x as usize, other_z as usize)? {
+                    writer.push_chunk(&compress(&raw.decompress()?)?, (other_x, other_z))?;
+                }
+            }
+        }
+    }
+    writer.push_chunk(&compressed, slot)?;
+
+    let temp_path = format!("{}.tmp", region_path);
+    writer.write(&mut File::create(&temp_path)?)?;
+    rename(&temp_path, &region_path)?;
+    Ok(())
+}
+
+fn compress(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut compressed = Vec::new();
+    let mut encoder = flate2::write::ZlibEncoder::new(&mut compressed, flate2::Compression::default());
+    encoder.write_all(data)?;
+    encoder.finish()?;
+    Ok(compressed)
+}
+
 impl WorldData {
     /*pub fn load_chunk(&mut self, x: i32, z: i32) -> anyhow::Result<()> {
         let (rx, rz) = (x.div_euclid(32), z.div_euclid(32));
@@ -201,30 +339,11 @@ impl WorldData {
     }*/
 
 
-    pub fn save_chunk(&self, x: i32, z: i32) -> anyhow::Result<()> {
-        let chunk = self.chunks_loaded.get(&(x,z)).expect("Chunk must be loaded");
-        let nbt = chunk_to_nbt(chunk);
-        create_dir_all("region")?;
-        let mut writer = RegionWriter::new();
-        let mut nbt_buf = Vec::new();
-        to_writer(&mut nbt_buf, &nbt)?;
-        let mut compressed = Vec::new();
-        {
-            let mut encoder = flate2::write::ZlibEncoder::new(&mut compressed, flate2::Compression::default());
-            encoder.write_all(&nbt_buf)?;
-            encoder.finish()?;
-        }
-        writer.push_chunk(&compressed, ((x & 31) as u8, (z & 31) as u8))?;
-        let mut out = File::create(format!("region/r.{}.{}.mca", x.div_euclid(32), z.div_euclid(32)))?;
-        writer.write(&mut out)?;
-        Ok(())
-    }
-
     /// Retourne l’index du bloc dans la palette pour un bloc aux coordonnées mondiales (wx, wy, wz)
     /// Retourne None si chunk non chargé ou coordonnées invalides
     pub fn get_block_at(&self, x: isize, y: isize, z: isize) -> BlockType {
-        if y >= WORLD_HEIGHT as isize || y < 0 {
-            return BlockType::Air;
+        if !self.config.contains_y(y) {
+            return BlockType::AIR;
         }
 
         let chunk_x = x.div_euclid(CHUNK_SIZE as isize);
@@ -232,30 +351,168 @@ impl WorldData {
 
         // Coordonnées locales dans le chunk
         let local_x = x.rem_euclid(CHUNK_SIZE as isize) as usize;
-        let local_y = y as usize;
+        let local_y = y;
         let local_z = z.rem_euclid(CHUNK_SIZE as isize) as usize;
 
         // Vérifie si le chunk est chargé
         if let Some(chunk) = self.chunks_loaded.get(&(chunk_x as i32, chunk_z as i32)) {
             return chunk.get_block_at(local_x, local_y, local_z);
         }
-        BlockType::Air
+        BlockType::AIR
+    }
+
+    /// Modifie le bloc aux coordonnées mondiales (wx, wy, wz) si le chunk est chargé.
+    /// Retourne None si rien n'a été modifié, sinon les sections (x, z, section_y) dont la lumière a changé
+    pub fn set_block(&mut self, wx: i32, wy: i32, wz: i32, block: BlockType) -> Option<HashSet<(i32, i32, i8)>> {
+        if !self.config.contains_y(wy as isize) {
+            return None;
+        }
+
+        let chunk_x = wx.div_euclid(CHUNK_SIZE as i32);
+        let chunk_z = wz.div_euclid(CHUNK_SIZE as i32);
+        let chunk = self.chunks_loaded.get_mut(&(chunk_x, chunk_z))?;
+
+        // Copie à l'écriture si une tâche de maillage détient encore ce chunk
+        let section_y = wy.div_euclid(SECTION_HEIGHT as i32) as i8;
+        let section = Arc::make_mut(chunk).section_mut(section_y)?;
+
+        let local_x = wx.rem_euclid(CHUNK_SIZE as i32) as usize;
+        let local_z = wz.rem_euclid(CHUNK_SIZE as i32) as usize;
+        section.set_block(local_x, wy.rem_euclid(SECTION_HEIGHT as i32) as usize, local_z, block);
+
+        // La lumière est mise à jour de proche en proche, y compris dans les chunks voisins
+        let mut lit_sections = HashSet::new();
+        relight_block(self, wx as isize, wy as isize, wz as isize, &mut lit_sections);
+        Some(lit_sections)
     }
 
-    /// Modifie le bloc aux coordonnées mondiales (wx, wy, wz) si le chunk est chargé
-    /// Retourne true si modification faite, false sinon
-    pub fn set_block(&mut self, wx: i32, wy: i32, wz: i32, palette_index: u8) {
+    /// Voisins directs (4-connexes) déjà chargés du chunk (x, z)
+    pub fn loaded_neighbors(&self, x: i32, z: i32) -> impl Iterator<Item = (i32, i32)> + '_ {
+        [(1, 0), (-1, 0), (0, 1), (0, -1)]
+            .into_iter()
+            .map(move |(dx, dz)| (x + dx, z + dz))
+            .filter(|pos| self.chunks_loaded.contains_key(pos))
+    }
 
+    /// Raccorde la lumière d'un chunk qui vient d'être ajouté avec celle de ses voisins déjà chargés.
+    /// Seules les cases de bordure qui peuvent éclairer la case d'en face (au moins 2 niveaux de plus)
+    /// servent de départ à la propagation.
+    pub fn stitch_light_borders(&mut self, x: i32, z: i32) {
+        let size = CHUNK_SIZE as isize;
+        let (base_x, base_z) = (x as isize * size, z as isize * size);
+        let (min_y, max_y) = (self.config.min_build_height as isize, self.config.max_build_height as isize);
+
+        // Pour chaque voisin : direction du voisin, première case du bord, pas le long du bord
+        let borders = [
+            ((1, 0), (base_x + size - 1, base_z), (0, 1)),
+            ((-1, 0), (base_x, base_z), (0, 1)),
+            ((0, 1), (base_x, base_z + size - 1), (1, 0)),
+            ((0, -1), (base_x, base_z), (1, 0)),
+        ];
+
+        for kind in [LightKind::Sky, LightKind::Block] {
+            let mut queue = VecDeque::new();
+            for ((out_x, out_z), (start_x, start_z), (along_x, along_z)) in borders {
+                if !self.chunks_loaded.contains_key(&(x + out_x as i32, z + out_z as i32)) {
+                    continue;
+                }
+                for i in 0..size {
+                    let (inner_x, inner_z) = (start_x + along_x * i, start_z + along_z * i);
+                    let (outer_x, outer_z) = (inner_x + out_x, inner_z + out_z);
+                    for y in min_y..max_y {
+                        let (Some(inner), Some(outer)) = (self.light(kind, inner_x, y, inner_z), self.light(kind, outer_x, y, outer_z)) else {
+                            continue;
+                        };
+                        if inner > outer + 1 {
+                            queue.push_back((inner_x, y, inner_z));
+                        } else if outer > inner + 1 {
+                            queue.push_back((outer_x, y, outer_z));
+                        }
+                    }
+                }
+            }
+            propagate_light(self, kind, &mut queue);
+        }
     }
 }
 
 // Convertit NBT (Value) ⇄ chunk simplifié
 fn parse_nbt_to_chunk(x:i32, z:i32, nbt: Value) -> Chunk {
-    // parsing minimal example – adapter selon structure NBT
-    Chunk { x, z, sections: vec![] }
+    let mut chunk = Chunk::new(x, z);
+    // Lumière absente ou tronquée dans au moins une section : tout le chunk est rééclairé
+    let mut light_valid = true;
+
+    let Value::Compound(root) = nbt else {
+        return chunk;
+    };
+    let Some(Value::List(sections)) = root.get("sections") else {
+        return chunk;
+    };
+
+    for section_nbt in sections {
+        let Value::Compound(section_nbt) = section_nbt else { continue };
+        let Some(Value::Byte(section_y)) = section_nbt.get("Y") else { continue };
+
+        let mut section = ChunkSection::new(*section_y);
+        if let Some(Value::List(palette)) = section_nbt.get("palette") {
+            section.set_palette(palette.iter().filter_map(|v| match v {
+                Value::String(name) => Some(name.clone()),
+                _ => None,
+            }).collect());
+        }
+        if let Some(Value::ByteArray(blocks)) = section_nbt.get("blocks") {
+            section.blocks = blocks.iter().map(|&b| b as u8).collect();
+        }
+        match (section_nbt.get("SkyLight"), section_nbt.get("BlockLight")) {
+            (Some(Value::ByteArray(sky_light)), Some(Value::ByteArray(block_light)))
+                if sky_light.len() == LIGHT_ARRAY_SIZE && block_light.len() == LIGHT_ARRAY_SIZE =>
+            {
+                section.sky_light = sky_light.iter().map(|&b| b as u8).collect();
+                section.block_light = block_light.iter().map(|&b| b as u8).collect();
+            }
+            _ => light_valid = false,
+        }
+
+        if section.blocks.len() == CHUNK_SIZE * CHUNK_SIZE * SECTION_HEIGHT && !section.palette().is_empty() {
+            chunk.sections.push(section);
+        } else {
+            error!("Section {} du chunk ({}, {}) invalide, ignorée", section_y, x, z);
+        }
+    }
+
+    if !light_valid && !chunk.sections.is_empty() {
+        error!("Lumière du chunk ({}, {}) absente ou invalide, recalculée", x, z);
+        for section in chunk.sections.iter_mut() {
+            section.sky_light.fill(0);
+            section.block_light.fill(0);
+        }
+        light_chunk(&mut chunk);
+    }
+
+    chunk
 }
 
 fn chunk_to_nbt(chunk: &Chunk) -> Value {
-    // création d'un Value::Compound détaillé
-    Value::Compound(Default::default())
-}
\ No newline at end of file
+    let sections = chunk.sections.iter().map(|section| {
+        let mut section_nbt = HashMap::new();
+        section_nbt.insert("Y".to_string(), Value::Byte(section.y));
+        section_nbt.insert(
+            "palette".to_string(),
+            Value::List(section.palette().iter().map(|name| Value::String(name.clone())).collect()),
+        );
+        section_nbt.insert("blocks".to_string(), Value::ByteArray(to_byte_array(&section.blocks)));
+        section_nbt.insert("SkyLight".to_string(), Value::ByteArray(to_byte_array(&section.sky_light)));
+        section_nbt.insert("BlockLight".to_string(), Value::ByteArray(to_byte_array(&section.block_light)));
+        Value::Compound(section_nbt)
+    }).collect();
+
+    let mut root = HashMap::new();
+    root.insert("xPos".to_string(), Value::Int(chunk.x));
+    root.insert("zPos".to_string(), Value::Int(chunk.z));
+    root.insert("sections".to_string(), Value::List(sections));
+    Value::Compound(root)
+}
+
+fn to_byte_array(bytes: &[u8]) -> ByteArray {
+    ByteArray::new(bytes.iter().map(|&b| b as i8).collect())
+}
@@ -0,0 +1,202 @@
+use bevy::math::{IVec3, Vec3};
+use crate::world::block::BlockType;
+use crate::world::load_save_chunk::WorldData;
+
+/// Longueur maximale d'un rayon, en blocs : une distance infinie ou invalide est ramenée à cette valeur
+pub const MAX_RAYCAST_DISTANCE: f32 = 1024.0;
+
+/// Bloc touché par un rayon
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RaycastHit {
+    pub position: IVec3,
+    /// Normale de la face touchée (zéro si le rayon part de l'intérieur du bloc)
+    pub normal: IVec3,
+    pub block: BlockType,
+    pub distance: f32,
+}
+
+/// Blocs que le rayon traverse sans s'arrêter (l'air est toujours traversé)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RaycastFilter {
+    pub ignore_fluids: bool,
+    pub ignore_non_solid: bool,
+}
+
+impl RaycastFilter {
+    fn stops_at(&self, block: BlockType) -> bool {
+        if block == BlockType::AIR {
+            return false;
+        }
+        let definition = block.definition();
+        !(self.ignore_fluids && definition.fluid) && !(self.ignore_non_solid && !definition.solid)
+    }
+}
+
+impl WorldData {
+    /// Premier bloc non-air le long du rayon, sur au plus `max_dist` blocs
+    pub fn raycast(&self, origin: Vec3, dir: Vec3, max_dist: f32) -> Option<RaycastHit> {
+        self.raycast_filtered(origin, dir, max_dist, RaycastFilter::default())
+    }
+
+    /// Parcours de la grille voxel par DDA (Amanatides & Woo), à travers les bordures de chunks.
+    /// Les chunks non chargés sont traversés comme de l'air. Retourne None pour une direction nulle,
+    /// et s'arrête dès que le rayon quitte le monde par le haut ou par le bas.
+    /// Si l'origine est déjà dans un bloc qui arrête le rayon, il est touché à distance 0 avec une normale nulle :
+    /// aucune face n'a été traversée.
+    pub fn raycast_filtered(&self, origin: Vec3, dir: Vec3, max_dist: f32, filter: RaycastFilter) -> Option<RaycastHit> {
+        let dir = dir.normalize_or_zero();
+        if dir == Vec3::ZERO || !origin.is_finite() {
+            return None;
+        }
+        let max_dist = if max_dist.is_nan() { 0.0 } else { max_dist.min(MAX_RAYCAST_DISTANCE) };
+        let (min_y, max_y) = (self.config.min_build_height, self.config.max_build_height);
+
+        let mut position = origin.floor().as_ivec3();
+        let step = IVec3::new(
+            dir.x.signum() as i32,
+            dir.y.signum() as i32,
+            dir.z.signum() as i32,
+        );
+
+        // Distance le long du rayon pour traverser une case entière sur chaque axe
+        let t_delta = Vec3::new(
+            if dir.x != 0.0 { (1.0 / dir.x).abs() } else { f32::INFINITY },
+            if dir.y != 0.0 { (1.0 / dir.y).abs() } else { f32::INFINITY },
+            if dir.z != 0.0 { (1.0 / dir.z).abs() } else { f32::INFINITY },
+        );
+
+        // Distance jusqu'à la première frontière de case sur chaque axe
+        let first_boundary = |p: f32, d: f32, cell: i32| -> f32 {
+            if d > 0.0 {
+                (cell as f32 + 1.0 - p) / d
+            } else if d < 0.0 {
+                (p - cell as f32) / -d
+            } else {
+                f32::INFINITY
+            }
+        };
+        let mut t_max = Vec3::new(
+            first_boundary(origin.x, dir.x, position.x),
+            first_boundary(origin.y, dir.y, position.y),
+            first_boundary(origin.z, dir.z, position.z),
+        );
+
+        let mut normal = IVec3::ZERO;
+        let mut distance = 0.0;
+
+        while distance <= max_dist {
+            // Hors du monde en s'éloignant : plus aucun bloc à toucher
+            if (position.y < min_y && step.y <= 0) || (position.y >= max_y && step.y >= 0) {
+                return None;
+            }
+
+            let block = self.get_block_at(position.x as isize, position.y as isize, position.z as isize);
+            if filter.stops_at(block) {
+                return Some(RaycastHit { position, normal, block, distance });
+            }
+
+            if t_max.x < t_max.y && t_max.x < t_max.z {
+                position.x += step.x;
+                distance = t_max.x;
+                t_max.x += t_delta.x;
+                normal = IVec3::new(-step.x, 0, 0);
+            } else if t_max.y < t_max.z {
+                position.y += step.y;
+                distance = t_max.y;
+                t_max.y += t_delta.y;
+                normal = IVec3::new(0, -step.y, 0);
+            } else {
+                position.z += step.z;
+                distance = t_max.z;
+                t_max.z += t_delta.z;
+                normal = IVec3::new(0, 0, -step.z);
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use crate::constants::CHUNK_SIZE;
+    use crate::world::chunk::{Chunk, ChunkSection};
+    use crate::world::world_config::WorldConfig;
+
+    /// Chunks d'air (-1..=1, -1..=1) sur la première section, avec quelques blocs posés (coordonnées mondiales)
+    fn world_with(blocks: &[(IVec3, BlockType)]) -> WorldData {
+        let mut world = WorldData::new(Arc::new(WorldConfig::default()));
+        let section_y = world.config.min_section();
+        for x in -1..=1 {
+            for z in -1..=1 {
+                let mut section = ChunkSection::new(section_y);
+                section.set_palette(vec![BlockType::AIR.to_string()]);
+                let mut chunk = Chunk::new(x, z);
+                chunk.sections.push(section);
+                world.chunks_loaded.insert((x, z), Arc::new(chunk));
+            }
+        }
+
+        let size = CHUNK_SIZE as i32;
+        for &(position, block) in blocks {
+            let chunk = world.chunks_loaded.get_mut(&(position.x.div_euclid(size), position.z.div_euclid(size))).unwrap();
+            let section = Arc::make_mut(chunk).section_mut(section_y).unwrap();
+            section.set_block(position.x.rem_euclid(size) as usize, (position.y - world.config.min_build_height) as usize, position.z.rem_euclid(size) as usize, block);
+        }
+        world
+    }
+
+    #[test]
+    fn crosses_chunk_borders_and_reports_the_face_hit() {
+        let world = world_with(&[(IVec3::new(17, 4, 3), BlockType::ROCK)]);
+
+        // Vers +x depuis le chunk (0, 0) : la face ouest du bloc, dans le chunk (1, 0)
+        let hit = world.raycast(Vec3::new(10.5, 4.5, 3.5), Vec3::X, 20.0).unwrap();
+        assert_eq!(hit.position, IVec3::new(17, 4, 3));
+        assert_eq!(hit.normal, IVec3::NEG_X);
+        assert!((hit.distance - 6.5).abs() < 1e-4);
+
+        // Depuis le chunk (-1, 0), à travers tout le chunk (0, 0), puis en sens inverse : la face est
+        let hit = world.raycast(Vec3::new(-3.5, 4.5, 3.5), Vec3::X, 30.0).unwrap();
+        assert_eq!(hit.position, IVec3::new(17, 4, 3));
+        let hit = world.raycast(Vec3::new(30.5, 4.5, 3.5), Vec3::NEG_X, 20.0).unwrap();
+        assert_eq!(hit.normal, IVec3::X);
+
+        // Par le dessus
+        let hit = world.raycast(Vec3::new(17.5, 10.0, 3.5), Vec3::NEG_Y, 20.0).unwrap();
+        assert_eq!(hit.normal, IVec3::Y);
+        assert!((hit.distance - 5.0).abs() < 1e-4);
+
+        // Trop court
+        assert!(world.raycast(Vec3::new(10.5, 4.5, 3.5), Vec3::X, 6.0).is_none());
+    }
+
+    #[test]
+    fn fluid_filter_sees_through_water() {
+        let world = world_with(&[(IVec3::new(2, 4, 0), BlockType::WATER), (IVec3::new(4, 4, 0), BlockType::SAND)]);
+        let origin = Vec3::new(0.5, 4.5, 0.5);
+
+        assert_eq!(world.raycast(origin, Vec3::X, 10.0).unwrap().block, BlockType::WATER);
+        let filter = RaycastFilter { ignore_fluids: true, ..Default::default() };
+        let hit = world.raycast_filtered(origin, Vec3::X, 10.0, filter).unwrap();
+        assert_eq!(hit.block, BlockType::SAND);
+        assert_eq!(hit.position, IVec3::new(4, 4, 0));
+    }
+
+    #[test]
+    fn degenerate_rays_end() {
+        let world = world_with(&[(IVec3::new(0, 4, 0), BlockType::ROCK)]);
+
+        assert!(world.raycast(Vec3::new(0.5, 4.5, 0.5), Vec3::ZERO, 10.0).is_none());
+        // Une distance infinie s'arrête au bout du monde chargé ou à la limite, sans boucler
+        assert!(world.raycast(Vec3::new(0.5, 8.5, 0.5), Vec3::Y, f32::INFINITY).is_none());
+        assert!(world.raycast(Vec3::new(0.5, 8.5, 0.5), Vec3::X, f32::INFINITY).is_none());
+        assert!(world.raycast(Vec3::new(0.5, 8.5, 0.5), Vec3::X, f32::NAN).is_none());
+
+        // Départ dans un bloc : touché tout de suite, sans face traversée
+        let hit = world.raycast(Vec3::new(0.5, 4.5, 0.5), Vec3::X, 10.0).unwrap();
+        assert_eq!((hit.position, hit.normal, hit.distance), (IVec3::new(0, 4, 0), IVec3::ZERO, 0.0));
+    }
+}
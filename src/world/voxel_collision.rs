@@ -0,0 +1,325 @@
+use std::collections::{HashMap, HashSet};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::{Collider, RigidBody};
+use crate::constants::{CHUNK_SIZE, SECTION_HEIGHT};
+use crate::render::chunk_loadings_mesh_logic::SectionToUpdateEvent;
+use crate::world::block::BlockRegistry;
+use crate::world::chunk::ChunkSection;
+use crate::world::chunk_loadings_logic::ViewDistance;
+use crate::world::chunk_state::{ChunkStatus, ChunkStatusChangedEvent};
+use crate::world::load_save_chunk::WorldData;
+
+/// Nombre maximal de colliders de section construits par frame, pour étaler le travail
+/// quand un corps entre dans une zone neuve
+const MAX_COLLIDERS_PER_FRAME: usize = 16;
+
+/// Collider d'une section, construit directement à partir de ses blocs
+#[derive(Component)]
+pub struct SectionCollider {
+    pub x: i32,
+    pub z: i32,
+    pub section_y: i8,
+}
+
+/// Sections dont les collisions sont construites, avec leur entité (None si la section n'a aucun bloc solide)
+#[derive(Resource, Default)]
+pub struct VoxelColliders {
+    pub sections: HashMap<(i32, i32, i8), Option<Entity>>,
+    /// Sections (x, z, section_y) des corps et rayon lors du dernier calcul des sections voulues.
+    /// None force un nouveau calcul.
+    last_query: Option<(Vec<(i32, i32, i32)>, i32)>,
+    /// Sections voulues pas encore construites, de la plus proche d'un corps à la plus lointaine
+    missing: Vec<(i32, i32, i8)>,
+}
+
+pub struct VoxelCollisionPlugin;
+
+impl Plugin for VoxelCollisionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<VoxelColliders>();
+        app.add_systems(Update, (rebuild_changed_colliders, update_voxel_colliders).chain());
+    }
+}
+
+/// Construit les collisions des sections proches des corps physiques et retire celles qui ne le sont plus.
+/// Les sections voulues ne sont recalculées que quand un corps change de section.
+fn update_voxel_colliders(
+    mut commands: Commands,
+    view_distance: Res<ViewDistance>,
+    world_data: Res<WorldData>,
+    mut colliders: ResMut<VoxelColliders>,
+    bodies: Query<&GlobalTransform, (With<RigidBody>, Without<SectionCollider>)>,
+) {
+    let radius = view_distance.simulation;
+    let size = CHUNK_SIZE as f32;
+    let height = SECTION_HEIGHT as f32;
+
+    let mut body_sections: Vec<(i32, i32, i32)> = bodies.iter().map(|body| {
+        let position = body.translation();
+        ((position.x / size).floor() as i32, (position.z / size).floor() as i32, (position.y / height).floor() as i32)
+    }).collect();
+    body_sections.sort_unstable();
+    body_sections.dedup();
+
+    let query = (body_sections, radius);
+    if colliders.last_query.as_ref() != Some(&query) {
+        let config = &world_data.config;
+        let (min_section, max_section) = (config.min_section() as i32, config.min_section() as i32 + config.section_count() as i32 - 1);
+        let body_sections = &query.0;
+
+        let mut wanted = HashSet::new();
+        for &(chunk_x, chunk_z, section_y) in body_sections {
+            for x in chunk_x - radius..=chunk_x + radius {
+                for z in chunk_z - radius..=chunk_z + radius {
+                    for y in (section_y - radius).max(min_section)..=(section_y + radius).min(max_section) {
+                        wanted.insert((x, z, y as i8));
+                    }
+                }
+            }
+        }
+
+        colliders.sections.retain(|key, entity| {
+            if wanted.contains(key) {
+                return true;
+            }
+            if let Some(entity) = entity {
+                commands.entity(*entity).despawn();
+            }
+            false
+        });
+
+        let mut missing: Vec<_> = wanted.into_iter().filter(|key| !colliders.sections.contains_key(key)).collect();
+        // Les sections sous les corps d'abord
+        missing.sort_by_key(|&(x, z, y)| {
+            body_sections.iter()
+                .map(|&(bx, bz, by)| (x - bx).pow(2) + (z - bz).pow(2) + (y as i32 - by).pow(2))
+                .min()
+        });
+        colliders.missing = missing;
+        colliders.last_query = Some(query);
+    }
+
+    let VoxelColliders { sections, missing, .. } = &mut *colliders;
+    let mut built = 0;
+    missing.retain(|&(x, z, section_y)| {
+        if built == MAX_COLLIDERS_PER_FRAME {
+            return true;
+        }
+        // Chunk pas encore chargé : on réessaiera à la frame suivante
+        let Some(section) = world_data.chunks_loaded.get(&(x, z)).and_then(|chunk| chunk.section(section_y)) else {
+            return true;
+        };
+        let entity = spawn_section_collider(&mut commands, x, z, section);
+        sections.insert((x, z, section_y), entity);
+        built += 1;
+        false
+    });
+}
+
+/// Reconstruit les collisions des sections modifiées par une pose de bloc, parmi celles qui les ont déjà.
+/// Celles des chunks déchargés sont retirées ; elles seront reconstruites à leur rechargement.
+fn rebuild_changed_colliders(
+    mut commands: Commands,
+    world_data: Res<WorldData>,
+    mut colliders: ResMut<VoxelColliders>,
+    mut section_events: EventReader<SectionToUpdateEvent>,
+    mut status_events: EventReader<ChunkStatusChangedEvent>,
+) {
+    let unloaded: HashSet<(i32, i32)> = status_events.read()
+        .filter(|event| event.to == Some(ChunkStatus::Unloading))
+        .map(|event| (event.x, event.z))
+        .collect();
+    if !unloaded.is_empty() {
+        colliders.sections.retain(|&(x, z, _), entity| {
+            if !unloaded.contains(&(x, z)) {
+                return true;
+            }
+            if let Some(entity) = entity {
+                commands.entity(*entity).despawn();
+            }
+            false
+        });
+        colliders.last_query = None;
+    }
+
+    let changed: HashSet<(i32, i32, i8)> = section_events.read()
+        .map(|event| (event.x, event.z, event.section_y))
+        .collect();
+
+    for key @ (x, z, section_y) in changed {
+        let Some(previous) = colliders.sections.get(&key).copied() else {
+            continue;
+        };
+        if let Some(entity) = previous {
+            commands.entity(entity).despawn();
+        }
+        match world_data.chunks_loaded.get(&(x, z)).and_then(|chunk| chunk.section(section_y)) {
+            Some(section) => {
+                let entity = spawn_section_collider(&mut commands, x, z, section);
+                colliders.sections.insert(key, entity);
+            }
+            None => {
+                colliders.sections.remove(&key);
+            }
+        }
+    }
+}
+
+fn spawn_section_collider(commands: &mut Commands, x: i32, z: i32, section: &ChunkSection) -> Option<Entity> {
+    let collider = section_collider(section)?;
+    let entity = commands.spawn((
+        collider,
+        Transform::from_xyz(
+            (x * CHUNK_SIZE as i32) as f32,
+            (section.y as i32 * SECTION_HEIGHT as i32) as f32,
+            (z * CHUNK_SIZE as i32) as f32,
+        ),
+        SectionCollider { x, z, section_y: section.y },
+    ));
+    Some(entity.id())
+}
+
+/// Collider composé des pavés de `collision_boxes`, None pour une section sans bloc solide
+pub fn section_collider(section: &ChunkSection) -> Option<Collider> {
+    let boxes = collision_boxes(section);
+    if boxes.is_empty() {
+        return None;
+    }
+    Some(Collider::compound(boxes.into_iter().map(|(min, max)| cuboid(min, max)).collect()))
+}
+
+/// Pavés (min, max) d'une section : les cubes solides sont fusionnés en boîtes aussi grandes que
+/// possible, les blocs à modèle ajoutent leurs boîtes de collision. Coordonnées locales à la section.
+pub fn collision_boxes(section: &ChunkSection) -> Vec<(Vec3, Vec3)> {
+    let registry = BlockRegistry::global();
+    let mut cuboids = Vec::new();
+
+    let mut solid = [false; CHUNK_SIZE * CHUNK_SIZE * SECTION_HEIGHT];
+    let index = |x: usize, y: usize, z: usize| (y * CHUNK_SIZE + z) * CHUNK_SIZE + x;
+    for y in 0..SECTION_HEIGHT {
+        for z in 0..CHUNK_SIZE {
+            for x in 0..CHUNK_SIZE {
+                let block = section.get_block(x, y, z);
+                if !block.definition().solid {
+                    continue;
+                }
+                match registry.model(block) {
+                    Some(model) => {
+                        let offset = Vec3::new(x as f32, y as f32, z as f32);
+                        for corners in &model.collision_boxes {
+                            let min = corners.iter().copied().fold(Vec3::INFINITY, Vec3::min) + offset;
+                            let max = corners.iter().copied().fold(Vec3::NEG_INFINITY, Vec3::max) + offset;
+                            cuboids.push((min, max));
+                        }
+                    }
+                    None => solid[index(x, y, z)] = true,
+                }
+            }
+        }
+    }
+
+    // Fusion gloutonne : on étend chaque boîte le long de x, puis de z, puis de y
+    for y in 0..SECTION_HEIGHT {
+        for z in 0..CHUNK_SIZE {
+            for x in 0..CHUNK_SIZE {
+                if !solid[index(x, y, z)] {
+                    continue;
+                }
+
+                let mut x_end = x + 1;
+                while x_end < CHUNK_SIZE && solid[index(x_end, y, z)] {
+                    x_end += 1;
+                }
+                let row_filled = |y: usize, z: usize, solid: &[bool]| (x..x_end).all(|x| solid[index(x, y, z)]);
+
+                let mut z_end = z + 1;
+                while z_end < CHUNK_SIZE && row_filled(y, z_end, &solid) {
+                    z_end += 1;
+                }
+                let mut y_end = y + 1;
+                while y_end < SECTION_HEIGHT && (z..z_end).all(|z| row_filled(y_end, z, &solid)) {
+                    y_end += 1;
+                }
+
+                for by in y..y_end {
+                    for bz in z..z_end {
+                        for bx in x..x_end {
+                            solid[index(bx, by, bz)] = false;
+                        }
+                    }
+                }
+                cuboids.push((
+                    Vec3::new(x as f32, y as f32, z as f32),
+                    Vec3::new(x_end as f32, y_end as f32, z_end as f32),
+                ));
+            }
+        }
+    }
+
+    cuboids
+}
+
+fn cuboid(min: Vec3, max: Vec3) -> (Vec3, Quat, Collider) {
+    let half = (max - min) / 2.0;
+    (min + half, Quat::IDENTITY, Collider::cuboid(half.x, half.y, half.z))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::block::BlockType;
+
+    fn section_with(blocks: &[((usize, usize, usize), BlockType)]) -> ChunkSection {
+        let mut section = ChunkSection::new(0);
+        section.set_palette(vec![BlockType::AIR.to_string()]);
+        for &((x, y, z), block) in blocks {
+            section.set_block(x, y, z, block);
+        }
+        section
+    }
+
+    fn block(name: &str) -> BlockType {
+        BlockRegistry::global().by_name(name).unwrap()
+    }
+
+    fn assert_boxes(section: &ChunkSection, expected: &[([f32; 3], [f32; 3])]) {
+        let mut boxes = collision_boxes(section);
+        boxes.sort_by(|a, b| a.0.to_array().partial_cmp(&b.0.to_array()).unwrap());
+        assert_eq!(boxes.len(), expected.len(), "{:?}", boxes);
+        for ((min, max), (expected_min, expected_max)) in boxes.iter().zip(expected) {
+            assert!(min.abs_diff_eq(Vec3::from(*expected_min), 1e-5), "{:?}", boxes);
+            assert!(max.abs_diff_eq(Vec3::from(*expected_max), 1e-5), "{:?}", boxes);
+        }
+    }
+
+    #[test]
+    fn solid_cubes_merge_into_one_box() {
+        let section = section_with(&[((1, 2, 3), BlockType::ROCK), ((2, 2, 3), BlockType::BRICK), ((1, 3, 3), BlockType::ROCK), ((2, 3, 3), BlockType::ROCK)]);
+        assert_boxes(&section, &[([1.0, 2.0, 3.0], [3.0, 4.0, 4.0])]);
+        assert!(section_collider(&section_with(&[])).is_none());
+    }
+
+    #[test]
+    fn model_blocks_collide_with_their_boxes() {
+        // Plante : aucune collision
+        assert_boxes(&section_with(&[((4, 4, 4), block("minecraft:tall_grass"))]), &[]);
+        // Dalle : la moitié basse du bloc
+        assert_boxes(&section_with(&[((4, 4, 4), block("minecraft:rock_slab"))]), &[([4.0, 4.0, 4.0], [5.0, 4.5, 5.0])]);
+        // Escalier : la dalle du bas et la marche du haut, côté est
+        assert_boxes(&section_with(&[((4, 4, 4), block("minecraft:rock_stairs"))]), &[
+            ([4.0, 4.0, 4.0], [5.0, 4.5, 5.0]),
+            ([4.5, 4.5, 4.0], [5.0, 5.0, 5.0]),
+        ]);
+    }
+
+    #[test]
+    fn fence_collides_with_its_post_only() {
+        let fence = block("minecraft:oak_fence");
+        // Deux poteaux voisins restent deux boîtes étroites, sans être fusionnés comme des cubes
+        let section = section_with(&[((3, 2, 4), fence), ((4, 2, 4), fence)]);
+        assert_boxes(&section, &[
+            ([3.375, 2.0, 4.375], [3.625, 3.0, 4.625]),
+            ([4.375, 2.0, 4.375], [4.625, 3.0, 4.625]),
+        ]);
+    }
+}
@@ -2,21 +2,39 @@ use bevy::prelude::*;
 use bevy::app::{App, Plugin};
 use crate::generation::chunk_generation_logic::ChunkGenerationPlugin;
 use crate::world::chunk_loadings_logic::ChunkLoadingsPlugin;
+use crate::world::chunk_queue::ChunkQueuePlugin;
+use crate::world::chunk_state::ChunkStatePlugin;
 use crate::render::chunk_loadings_mesh_logic::GenerateMeshChunksPlugin;
-use crate::world::load_save_chunk::{WorldData, WorldDataPlugin};
+use crate::render::lod::ChunkLodPlugin;
+use crate::render::underwater::UnderwaterPlugin;
+use crate::render::export::WorldExportPlugin;
+use crate::world::block::BlockRegistry;
+use crate::world::load_save_chunk::WorldDataPlugin;
 use crate::world::skybox::SkyboxPlugin;
+use crate::world::voxel_collision::VoxelCollisionPlugin;
+use crate::world::world_config::WorldConfig;
 
 // --- PLUGIN ---
 pub struct WorldPlugin;
 
 impl Plugin for WorldPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(WorldData::default());
+        app.insert_resource(BlockRegistry::global().clone());
+        // Une configuration déjà insérée (tests, outils) remplace celle du disque
+        if !app.world().contains_resource::<WorldConfig>() {
+            app.insert_resource(WorldConfig::load());
+        }
         app.add_plugins(WorldDataPlugin);
         app.add_plugins(ChunkLoadingsPlugin);
+        app.add_plugins(ChunkQueuePlugin);
+        app.add_plugins(ChunkStatePlugin);
         app.add_plugins(ChunkGenerationPlugin);
         app.add_plugins(GenerateMeshChunksPlugin);
+        app.add_plugins(ChunkLodPlugin);
         app.add_plugins(SkyboxPlugin);
+        app.add_plugins(UnderwaterPlugin);
+        app.add_plugins(WorldExportPlugin);
+        app.add_plugins(VoxelCollisionPlugin);
     }
 }
 
@@ -0,0 +1,131 @@
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+use bevy::log::{info, warn};
+use bevy::prelude::Resource;
+use flate2::read::GzDecoder;
+use serde::Deserialize;
+use crate::constants::{CHUNK_SIZE, SECTION_HEIGHT};
+
+pub const LEVEL_DAT_PATH: &str = "level.dat";
+pub const WORLD_CONFIG_PATH: &str = "config/world.json";
+
+/// Dimensions du monde. Lues dans `level.dat` pour un monde existant,
+/// sinon dans `config/world.json`, sinon valeurs par défaut.
+/// Les hauteurs de construction doivent être des multiples de 16 (une section).
+/// Ressource lue par les systèmes ; les tâches asynchrones en reçoivent un `Arc` (voir `WorldData::config`).
+#[derive(Resource, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct WorldConfig {
+    #[serde(alias = "MinBuildHeight")]
+    pub min_build_height: i32,
+    #[serde(alias = "MaxBuildHeight")]
+    pub max_build_height: i32, // exclusif
+    #[serde(alias = "SeaLevel")]
+    pub sea_level: i32,
+    /// Demi-largeur du monde en blocs, centrée sur l'origine
+    #[serde(alias = "WorldBorder")]
+    pub world_border: i32,
+    /// Rayon de chargement, en chunks : au-delà de `meshing_distance`, les chunks sont affichés en LOD
+    #[serde(alias = "ViewDistance")]
+    pub view_distance: i32,
+    /// Rayon, en chunks, des chunks maillés en pleine résolution
+    #[serde(alias = "MeshingDistance")]
+    pub meshing_distance: i32,
+    /// Rayon, en chunks autour de chaque corps physique, dans lequel les collisions des blocs sont construites
+    #[serde(alias = "SimulationDistance")]
+    pub simulation_distance: i32,
+}
+
+impl Default for WorldConfig {
+    fn default() -> Self {
+        Self {
+            min_build_height: 0,
+            max_build_height: 384, // 24 sections verticales (384 = 16 * 24)
+            sea_level: 126,
+            world_border: 16000,
+            view_distance: 20,
+            meshing_distance: 8,
+            simulation_distance: 2,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct LevelDat {
+    #[serde(rename = "Data")]
+    data: WorldConfig,
+}
+
+impl WorldConfig {
+    pub fn load() -> Self {
+        let config = if Path::new(LEVEL_DAT_PATH).exists() {
+            Self::from_level_dat(Path::new(LEVEL_DAT_PATH))
+        } else if Path::new(WORLD_CONFIG_PATH).exists() {
+            Self::from_json_file(Path::new(WORLD_CONFIG_PATH))
+        } else {
+            Ok(Self::default())
+        };
+
+        match config.and_then(|c| c.validated()) {
+            Ok(config) => {
+                info!("Dimensions du monde : {:?}", config);
+                config
+            }
+            Err(e) => {
+                warn!("Configuration du monde invalide ({}), valeurs par défaut utilisées", e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn from_json_file(path: &Path) -> anyhow::Result<Self> {
+        let json_str = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json_str)?)
+    }
+
+    pub fn from_level_dat(path: &Path) -> anyhow::Result<Self> {
+        let mut data = Vec::new();
+        GzDecoder::new(File::open(path)?).read_to_end(&mut data)?;
+        let level: LevelDat = fastnbt::from_bytes(&data)?;
+        Ok(level.data)
+    }
+
+    fn validated(self) -> anyhow::Result<Self> {
+        let section = SECTION_HEIGHT as i32;
+        if self.min_build_height % section != 0 || self.max_build_height % section != 0 {
+            anyhow::bail!("les hauteurs de construction doivent être des multiples de {}", section);
+        }
+        if self.min_build_height >= self.max_build_height {
+            anyhow::bail!("hauteur minimale supérieure à la hauteur maximale");
+        }
+        let sections = (self.max_build_height - self.min_build_height) / section;
+        if self.min_build_height / section < i8::MIN as i32 || self.max_build_height / section > i8::MAX as i32 || sections <= 0 {
+            anyhow::bail!("trop de sections verticales");
+        }
+        Ok(self)
+    }
+
+    pub fn height(&self) -> i32 {
+        self.max_build_height - self.min_build_height
+    }
+
+    pub fn min_section(&self) -> i8 {
+        (self.min_build_height / SECTION_HEIGHT as i32) as i8
+    }
+
+    pub fn section_count(&self) -> usize {
+        (self.height() / SECTION_HEIGHT as i32) as usize
+    }
+
+    pub fn contains_y(&self, y: isize) -> bool {
+        y >= self.min_build_height as isize && y < self.max_build_height as isize
+    }
+
+    /// Le chunk (x, z) est-il entièrement à l'intérieur de la bordure du monde ?
+    pub fn contains_chunk(&self, x: i32, z: i32) -> bool {
+        let size = CHUNK_SIZE as i32;
+        let border = self.world_border;
+        x * size >= -border && (x + 1) * size <= border && z * size >= -border && (z + 1) * size <= border
+    }
+}